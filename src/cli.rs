@@ -1,12 +1,12 @@
 use clap::Parser;
 
-use crate::helpers::File;
+use crate::helpers::{Encoding, File};
 
 #[derive(Parser)]
 #[command(version, about)]
 pub struct Args {
-    /// The file to view. If nothing is specified, use input from STDIN
-    pub file: Option<File>,
+    /// The files to view. If nothing is specified, use input from STDIN
+    pub files: Vec<File>,
 
     /// Show line numbers (aliases: --line-numbers, --numbers)
     #[clap(short = 'l', long, aliases=["line-numbers", "numbers"])]
@@ -16,10 +16,47 @@ pub struct Args {
     #[clap(short = 'b', long)]
     pub show_borders: bool,
 
-    /// Pass the contents through without running the interactive Pager
+    /// Wrap long lines onto the following rows instead of cutting them off
+    #[clap(short, long)]
+    pub wrap: bool,
+
+    /// The number of columns between tab stops
+    #[clap(long, value_name = "N", default_value_t = 8)]
+    pub tabs: usize,
+
+    /// Show colours and styles from ANSI escape codes. Other control characters are still shown as ^X
+    #[clap(short = 'R', long, conflicts_with = "raw")]
+    pub ansi: bool,
+
+    /// Pass all control characters through to the terminal as they are. May mess up the screen
+    #[clap(short = 'r', long)]
+    pub raw: bool,
+
+    /// The character encoding of the input: auto, utf-8, utf-16le, utf-16be or latin1.
+    /// By default, the input is UTF-8 unless it starts with a UTF-16 byte order mark
+    #[clap(short, long, value_name = "ENCODING", default_value = "auto")]
+    pub encoding: Encoding,
+
+    /// Don't run the files through the preprocessor set in the SCANOPEN environment variable.
+    /// Like LESSOPEN, `|command %s` reads the output of the command instead of the file,
+    /// while `command %s` prints the path of a file to read instead. That file is left for the command to clean up
+    #[clap(short = 'L', long)]
+    pub no_preprocessor: bool,
+
+    /// Pass the contents through without running the interactive Pager.
+    /// They're passed through as they are, without being decompressed or run through the preprocessor
     #[clap(short, long, aliases=["skip", "no-page"])]
     pub passthrough: bool,
 
+    /// Ignore case when searching. By default, searches are case-insensitive
+    /// unless the query contains an uppercase letter (smart-case)
+    #[clap(short = 'i', long)]
+    pub ignore_case: bool,
+
+    /// Keep reading new contents as they are appended to the input, like `tail -f`
+    #[clap(short, long)]
+    pub follow: bool,
+
     /// Read the entire file in one go
     #[clap(short, long)]
     pub all: bool,
@@ -1,50 +1,329 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
 /// Character that denotes the starts of escape codes
 const ESC: char = '\x1b';
+/// The character that `man` and `nroff` use to strike one character over another
+const BACKSPACE: char = '\x08';
+/// The escape code that resets all colours and attributes
+const RESET: &str = "\x1b[0m";
 
-/// Determine the length of characters in an ANSI string that are visible in the terminal
-pub fn visible_width(s: &str) -> usize {
-    let mut width: usize = 0;
-    let mut chars = s.chars();
+/// How the control characters in the contents, escape codes included, reach the terminal
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum ControlChars {
+    /// Show them all in caret notation (e.g. `^[` or `^M`), so that they can't mess up the screen
+    #[default]
+    Caret,
+    /// Pass the colour and style escape codes (SGR) through, and show the rest in caret notation
+    Sgr,
+    /// Pass everything through to the terminal as it is
+    Raw,
+}
+
+/// A piece of an ANSI string: either an escape code, or a grapheme cluster (a user-perceived character)
+#[derive(Debug, PartialEq, Eq)]
+enum Token<'a> {
+    Escape(&'a str),
+    Grapheme(&'a str),
+}
+
+/// Split an ANSI string into escape codes and grapheme clusters
+fn tokens(s: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = s;
+    while !rest.is_empty() {
+        let text = match rest.find(ESC) {
+            Some(0) => {
+                let len = escape_len(rest);
+                tokens.push(Token::Escape(&rest[..len]));
+                rest = &rest[len..];
+                continue;
+            }
+            Some(i) => &rest[..i],
+            None => rest,
+        };
+        tokens.extend(text.graphemes(true).map(Token::Grapheme));
+        rest = &rest[text.len()..];
+    }
+    tokens
+}
+
+/// The length of the escape code at the start of the string
+fn escape_len(s: &str) -> usize {
+    let mut chars = s.char_indices().skip(1);
+    match chars.next() {
+        // Control Sequence Introducer: continue until `\x40-\x7E` (ASCII @A–Z[\]^_`a–z{|}~).
+        // See https://en.wikipedia.org/wiki/ANSI_escape_code#CSI_(Control_Sequence_Introducer)_sequences
+        Some((_, '[')) => chars
+            .find(|(_, c)| matches!(c, '\x40'..='\x7E'))
+            .map_or(s.len(), |(i, c)| i + c.len_utf8()),
+        // Control strings (OSC, DCS, SOS, PM and APC): continue until the String Terminator `ESC \`.
+        // Operating System Commands (e.g. setting the window title) may end with a bell instead
+        Some((_, ']' | 'P' | 'X' | '^' | '_')) => [
+            s.find('\x07').map(|i| i + 1),
+            s[1..].find("\x1b\\").map(|i| i + 3),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(s.len()),
+        // Other escape codes are made up of a single character after the ESC
+        Some((i, c)) => i + c.len_utf8(),
+        None => ESC.len_utf8(),
+    }
+}
+
+/// Returns true if the string starts with a colour or style escape code (e.g. `ESC[1;31m`)
+fn is_sgr(s: &str) -> bool {
+    let len = escape_len(s);
+    s.starts_with("\x1b[")
+        && s[..len].ends_with('m')
+        && s[2..len - 1]
+            .bytes()
+            .all(|b| b.is_ascii_digit() || b == b';' || b == b':')
+}
 
-    while let Some(c) = chars.next() {
-        // If we have not encountered a ESC yet ...
-        if c != ESC {
-            width += 1
+/// Tabs are left alone, as they are expanded to spaces later on
+fn is_control(c: char) -> bool {
+    c != '\t' && c.is_control()
+}
+
+/// The caret notation of a control character: `^@` to `^_` and `^?`, or its code point if there is none
+fn caret(c: char) -> String {
+    match c as u32 {
+        n @ 0x00..=0x1F => format!("^{}", char::from(n as u8 + 0x40)),
+        0x7F => String::from("^?"),
+        n => format!("<U+{n:04X}>"),
+    }
+}
+
+/// Make the control characters in the text safe to print, according to the mode.
+/// Unless everything is passed through, backspace overstrikes are turned into bold and underline.
+pub fn sanitize(s: &str, mode: ControlChars) -> String {
+    if mode == ControlChars::Raw || !s.contains(is_control) {
+        return s.to_string();
+    }
+    let mut sanitized = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find(is_control) {
+        sanitized.push_str(&rest[..i]);
+        rest = &rest[i..];
+        if mode == ControlChars::Sgr && is_sgr(rest) {
+            let len = escape_len(rest);
+            sanitized.push_str(&rest[..len]);
+            rest = &rest[len..];
+            continue;
+        }
+        let c = rest.chars().next().unwrap_or_default();
+        rest = &rest[c.len_utf8()..];
+        // Keep the backspaces that strike one character over another, for `overstrike` to style
+        let between_chars = !sanitized.is_empty() && rest.starts_with(|c| !is_control(c));
+        if c == BACKSPACE && between_chars {
+            sanitized.push(c);
         } else {
-            // .. otherwise, we hit the start of an ESC sequence
-            'esc_sequence: while let Some(c) = chars.next() {
-                match c {
-                    // Control Sequence Introducer: continue until `\x40-\x7E` (ASCII @A–Z[\]^_`a–z{|}~).
-                    // See https://en.wikipedia.org/wiki/ANSI_escape_code#CSI_(Control_Sequence_Introducer)_sequences
-                    '[' => {
-                        loop {
-                            let next = chars.next();
-                            if matches!(next, Some('\x40'..='\x7E') | None) {
-                                break 'esc_sequence; // Break as soon as we encounter the end of an ansi-code
-                            }
-                        }
-                    }
-                    _ => width += 1,
-                }
+            sanitized.push_str(&caret(c));
+        }
+    }
+    sanitized.push_str(rest);
+    if sanitized.contains(BACKSPACE) {
+        sanitized = overstrike(&sanitized);
+    }
+    sanitized
+}
+
+/// Turn the backspace overstrikes used by `man` and `nroff` into escape codes:
+/// a character struck over itself (`X\bX`) is bold, and one struck over an underscore (`_\bX`) is underlined
+fn overstrike(s: &str) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    let mut styled = String::with_capacity(s.len());
+    // Whether bold and underline are on
+    let mut current = (false, false);
+    let mut i = 0;
+    while i < chars.len() {
+        let mut c = chars[i];
+        let (mut bold, mut underline) = (false, false);
+        i += 1;
+        while i + 1 < chars.len() && chars[i] == BACKSPACE {
+            let over = chars[i + 1];
+            if over == c {
+                bold = true;
+            } else if c == '_' {
+                underline = true;
+                c = over;
+            } else if over == '_' {
+                underline = true;
+            } else {
+                c = over; // Anything else simply replaces the character
             }
+            i += 2;
         }
+        if bold != current.0 {
+            styled.push_str(if bold { "\x1b[1m" } else { "\x1b[22m" });
+        }
+        if underline != current.1 {
+            styled.push_str(if underline { "\x1b[4m" } else { "\x1b[24m" });
+        }
+        current = (bold, underline);
+        styled.push(c);
+    }
+    if current.0 {
+        styled.push_str("\x1b[22m");
+    }
+    if current.1 {
+        styled.push_str("\x1b[24m");
+    }
+    styled
+}
+
+/// The visible text of an ANSI string, without its escape codes, along with the offset in the string of each of its bytes.
+/// Returns None if the string has no escape codes, as it is its own visible text.
+pub fn strip_escapes(s: &str) -> Option<(String, Vec<usize>)> {
+    if !s.contains(ESC) {
+        return None;
     }
+    let mut text = String::with_capacity(s.len());
+    let mut offsets = Vec::with_capacity(s.len());
+    let mut pos = 0;
+    for token in tokens(s) {
+        match token {
+            Token::Escape(code) => pos += code.len(),
+            Token::Grapheme(g) => {
+                text.push_str(g);
+                offsets.extend(pos..pos + g.len());
+                pos += g.len();
+            }
+        }
+    }
+    Some((text, offsets))
+}
 
-    width
+/// The number of terminal columns the grapheme cluster takes up (e.g. 2 for CJK and most emoji, 1 for a letter with combining marks)
+pub fn grapheme_width(g: &str) -> usize {
+    g.width()
+}
+
+/// Determine the number of terminal columns taken up by the visible characters of an ANSI string
+pub fn visible_width(s: &str) -> usize {
+    tokens(s)
+        .into_iter()
+        .map(|token| match token {
+            Token::Escape(_) => 0,
+            Token::Grapheme(g) => grapheme_width(g),
+        })
+        .sum()
 }
 
+/// Cut an ANSI string down to the given number of terminal columns.
+/// Keeps the escape codes that come after the cut, so that colours are still reset.
 pub fn truncate_visible(s: &str, width: usize) -> String {
     let mut trunc = String::new();
-    let mut chars = s.chars();
-    while let Some(c) = chars.next() {
-        trunc.push(c);
-        if visible_width(&trunc) >= width {
-            break;
+    let mut used = 0;
+    let mut full = false;
+    for token in tokens(s) {
+        match token {
+            Token::Escape(code) => trunc.push_str(code),
+            Token::Grapheme(g) if !full => {
+                let w = grapheme_width(g);
+                if used + w > width {
+                    full = true; // A wide character that doesn't fit is left out entirely
+                    continue;
+                }
+                trunc.push_str(g);
+                used += w;
+            }
+            Token::Grapheme(_) => {}
         }
     }
     trunc
 }
 
+/// Replace the tabs in an ANSI string with spaces, up to the next tab stop.
+/// Tab stops are every `tabstop` columns.
+pub fn expand_tabs(s: &str, tabstop: usize) -> String {
+    if !s.contains('\t') {
+        return s.to_string();
+    }
+    let tabstop = tabstop.max(1);
+    let mut expanded = String::new();
+    let mut col = 0;
+    for token in tokens(s) {
+        match token {
+            Token::Escape(code) => expanded.push_str(code),
+            Token::Grapheme("\t") => {
+                let n = tabstop - col % tabstop;
+                expanded.push_str(&" ".repeat(n));
+                col += n;
+            }
+            Token::Grapheme(g) => {
+                expanded.push_str(g);
+                col += grapheme_width(g);
+            }
+        }
+    }
+    expanded
+}
+
+/// Drop the first `cols` terminal columns of an ANSI string.
+/// Keeps all of the escape codes, so that colours which started before the cut still apply.
+/// A wide character cut in half leaves spaces in the columns it would have taken up.
+pub fn skip_visible(s: &str, cols: usize) -> String {
+    let mut rest = String::new();
+    let mut skipped = 0;
+    for token in tokens(s) {
+        match token {
+            Token::Escape(code) => rest.push_str(code),
+            Token::Grapheme(g) if skipped >= cols => rest.push_str(g),
+            Token::Grapheme(g) => {
+                skipped += grapheme_width(g);
+                if skipped > cols {
+                    rest.push_str(&" ".repeat(skipped - cols));
+                }
+            }
+        }
+    }
+    rest
+}
+
+/// Split an ANSI string into parts of the given visible width (a part may be narrower when it's followed by a wide character).
+/// Each part starts with the escape codes in effect where it starts, and resets them at its end.
+pub fn wrap_visible(s: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut parts = Vec::new();
+    let mut part = String::new();
+    let mut part_width = 0;
+    let mut active = String::new(); // The escape codes in effect
+
+    for token in tokens(s) {
+        match token {
+            // Carry escape codes over as they are, remembering them for the next parts
+            Token::Escape(code) => {
+                if code == RESET || code == "\x1b[m" {
+                    active.clear();
+                } else {
+                    active.push_str(code);
+                }
+                part.push_str(code);
+            }
+            Token::Grapheme(g) => {
+                // Start a new part once this one is full
+                let w = grapheme_width(g);
+                if part_width + w > width && part_width > 0 {
+                    if !active.is_empty() {
+                        part.push_str(RESET);
+                    }
+                    parts.push(std::mem::replace(&mut part, active.clone()));
+                    part_width = 0;
+                }
+                part.push_str(g);
+                part_width += w;
+            }
+        }
+    }
+
+    parts.push(part);
+    parts
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -66,4 +345,113 @@ mod tests {
         let ansi_str = "\x1b[31mHello World!\x1b[0m";
         assert_eq!(str.len(), visible_width(ansi_str))
     }
+
+    #[test]
+    fn should_wrap_into_parts_of_the_given_width() {
+        assert_eq!(vec!["abc", "def", "g"], wrap_visible("abcdefg", 3));
+        assert_eq!(vec![""], wrap_visible("", 3));
+    }
+
+    #[test]
+    fn should_carry_escape_codes_over_to_the_next_part() {
+        assert_eq!(
+            vec!["a\x1b[31mb\x1b[0m", "\x1b[31mc\x1b[0md"],
+            wrap_visible("a\x1b[31mbc\x1b[0md", 2)
+        );
+    }
+
+    #[test]
+    fn should_count_wide_characters_as_two_columns() {
+        assert_eq!(4, visible_width("日本"));
+        assert_eq!(2, visible_width("🦀"));
+    }
+
+    #[test]
+    fn should_count_combining_characters_with_their_base() {
+        assert_eq!(4, visible_width("cafe\u{301}"));
+    }
+
+    #[test]
+    fn should_truncate_without_splitting_wide_characters() {
+        assert_eq!("a日", truncate_visible("a日本", 4));
+        assert_eq!(
+            "\x1b[31ma\x1b[0m",
+            truncate_visible("\x1b[31mabc\x1b[0m", 1)
+        );
+    }
+
+    #[test]
+    fn should_move_wide_characters_that_do_not_fit_to_the_next_part() {
+        assert_eq!(vec!["a", "日", "本"], wrap_visible("a日本", 2));
+    }
+
+    #[test]
+    fn should_skip_columns_and_keep_the_escape_codes() {
+        assert_eq!("\x1b[31mcd\x1b[0m", skip_visible("\x1b[31mabcd\x1b[0m", 2));
+        assert_eq!("", skip_visible("abc", 5));
+    }
+
+    #[test]
+    fn should_replace_a_wide_character_cut_in_half_with_a_space() {
+        assert_eq!(" 本", skip_visible("日本", 1));
+        assert_eq!("本", skip_visible("日本", 2));
+    }
+
+    #[test]
+    fn should_show_control_characters_in_caret_notation() {
+        assert_eq!(
+            "^[[31mred^[[0m^M^?",
+            sanitize("\x1b[31mred\x1b[0m\r\x7f", ControlChars::Caret)
+        );
+        assert_eq!("a\tb<U+009B>", sanitize("a\tb\u{9b}", ControlChars::Caret));
+    }
+
+    #[test]
+    fn should_only_pass_the_colour_codes_through() {
+        assert_eq!(
+            "\x1b[1;31mred\x1b[m^[[2J^[]0;title^G",
+            sanitize(
+                "\x1b[1;31mred\x1b[m\x1b[2J\x1b]0;title\x07",
+                ControlChars::Sgr
+            )
+        );
+        let raw = "\x1b[2J\x1b]0;title\x07";
+        assert_eq!(raw, sanitize(raw, ControlChars::Raw));
+    }
+
+    #[test]
+    fn should_turn_overstrikes_into_bold_and_underline() {
+        assert_eq!(
+            "\x1b[1mNAME\x1b[22m \x1b[4mls\x1b[24m",
+            sanitize("N\x08NA\x08AM\x08ME\x08E _\x08l_\x08s", ControlChars::Caret)
+        );
+        assert_eq!("\x1b[1m\x1b[4mx\x1b[22m\x1b[24m", overstrike("_\x08x\x08x"));
+        assert_eq!("^Hx^H", sanitize("\x08x\x08", ControlChars::Caret));
+    }
+
+    #[test]
+    fn should_strip_the_escape_codes_keeping_the_offsets_of_the_text() {
+        let (text, offsets) = strip_escapes("a\x1b[1mbc\x1b[22m").unwrap();
+        assert_eq!("abc", text);
+        assert_eq!(vec![0, 5, 6], offsets);
+        assert_eq!(None, strip_escapes("plain"));
+    }
+
+    #[test]
+    fn should_skip_over_control_strings() {
+        assert_eq!(4, visible_width("\x1b]0;title\x07text"));
+        assert_eq!(
+            4,
+            visible_width("\x1b]8;;https://example.com\x1b\\link\x1b]8;;\x1b\\")
+        );
+    }
+
+    #[test]
+    fn should_expand_tabs_to_the_next_tab_stop() {
+        assert_eq!("a   b       c", expand_tabs("a\tb\t\tc", 4));
+        assert_eq!(
+            "\x1b[31mab\x1b[0m  c",
+            expand_tabs("\x1b[31mab\x1b[0m\tc", 4)
+        );
+    }
 }
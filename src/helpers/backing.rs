@@ -0,0 +1,103 @@
+use super::Source;
+
+/// Keeps the raw contents read from the input, so that any part of them can be read again later.
+/// The contents are read back from the file they came from where possible, and kept in memory otherwise.
+#[derive(Default)]
+pub struct Backing {
+    /// The file the contents can be read back from, if any
+    source: Option<Source>,
+    /// The contents that can't be read back from the file, which start at the source's limit
+    memory: Vec<u8>,
+    /// The length of the contents
+    len: usize,
+}
+
+impl Backing {
+    pub fn new(source: Option<Source>) -> Self {
+        Self {
+            source,
+            ..Default::default()
+        }
+    }
+
+    /// The length of the contents
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The file the contents can be skipped through in, as long as they still come from it
+    pub fn source(&self) -> Option<&Source> {
+        self.source.as_ref().filter(|source| !source.is_rotated())
+    }
+
+    /// The offset from which the contents are kept in memory, as they can't be read back from the file
+    fn in_memory(&self) -> usize {
+        self.source
+            .as_ref()
+            .map_or(0, |source| source.limit().try_into().unwrap_or(usize::MAX))
+    }
+
+    /// The end of the contents that were lost when the file was truncated, which can't be read back anymore
+    pub fn lost(&self) -> usize {
+        match &self.source {
+            Some(source) if source.is_truncated() => self.in_memory().min(self.len),
+            _ => 0,
+        }
+    }
+
+    /// Add the next of the contents read from the input
+    pub fn push(&mut self, bytes: &[u8]) {
+        let skip = self.in_memory().saturating_sub(self.len).min(bytes.len());
+        self.memory.extend_from_slice(&bytes[skip..]);
+        self.len += bytes.len();
+    }
+
+    /// Account for the contents up to the given offset, which were skipped rather than read from the input.
+    /// They have to be in the file, to be read back from it.
+    pub fn skip_to(&mut self, offset: usize) {
+        if offset <= self.in_memory() {
+            self.len = self.len.max(offset);
+        }
+    }
+
+    /// Read back the contents at the offset, up to the given length.
+    /// The contents that were [lost](Self::lost) when the file was truncated are left out.
+    pub fn read(&self, offset: usize, len: usize) -> std::io::Result<Vec<u8>> {
+        let in_memory = self.in_memory();
+        let end = (offset + len).min(self.len);
+        let split = in_memory.clamp(offset, end.max(offset));
+        let mut bytes = vec![0; split - offset];
+        if let Some(source) = &self.source {
+            let n = match source.is_truncated() {
+                true => 0,
+                false => source.read_at(offset as u64, &mut bytes)?,
+            };
+            bytes.truncate(n);
+            // What was read may have come from the file after it was truncated
+            if !bytes.is_empty() && source.check_truncated(self.len as u64) {
+                bytes.clear();
+            }
+        }
+        if split < end {
+            bytes.extend_from_slice(&self.memory[split - in_memory..end - in_memory]);
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_keep_the_contents_of_a_stream_in_memory() {
+        let mut backing = Backing::default();
+        backing.push(b"one\n");
+        backing.push(b"two\n");
+        assert_eq!(8, backing.len());
+        assert_eq!(b"e\ntw", backing.read(2, 4).unwrap().as_slice());
+        assert_eq!(b"o\n", backing.read(6, 10).unwrap().as_slice());
+        assert!(backing.read(9, 2).unwrap().is_empty());
+        assert!(backing.source().is_none());
+    }
+}
@@ -32,21 +32,17 @@ impl Default for Borders {
 impl Borders {
     /// Draw the top border
     pub fn top(&self, width: usize) -> String {
-        format!(
-            "{}{}{}",
-            style(&self.top_left).dark_grey(),
-            style(&self.top.repeat(width - 2)).dark_grey(),
-            style(&self.top_right).dark_grey()
-        )
+        edge(&self.top_left, &self.top, &self.top_right, width)
     }
 
     /// Calculate the reduction in height due to the borders
     pub fn height_reduction(&self) -> usize {
         let mut reduction = 0;
-        if self.top != "" || self.top_left != "" || self.top_right != "" {
+        if !self.top.is_empty() || !self.top_left.is_empty() || !self.top_right.is_empty() {
             reduction += 1;
         }
-        if self.bottom != "" || self.bottom_left != "" || self.bottom_right != "" {
+        if !self.bottom.is_empty() || !self.bottom_left.is_empty() || !self.bottom_right.is_empty()
+        {
             reduction += 1;
         }
         reduction
@@ -55,10 +51,10 @@ impl Borders {
     /// Calculate the reduction in width due to borders
     pub fn width_reduction(&self) -> usize {
         let mut reduction = 0;
-        if self.left != "" || self.top_left != "" || self.bottom_left != "" {
+        if !self.left.is_empty() || !self.top_left.is_empty() || !self.bottom_left.is_empty() {
             reduction += visible_width(&self.left);
         }
-        if self.right != "" || self.top_right != "" || self.bottom_right != "" {
+        if !self.right.is_empty() || !self.top_right.is_empty() || !self.bottom_right.is_empty() {
             reduction += visible_width(&self.right);
         }
         reduction
@@ -66,11 +62,20 @@ impl Borders {
 
     /// Draw the bottom border
     pub fn bottom(&self, width: usize) -> String {
-        format!(
-            "{}{}{}",
-            style(&self.bottom_left).dark_grey(),
-            style(&self.bottom.repeat(width - 2)).dark_grey(),
-            style(&self.bottom_right).dark_grey()
-        )
+        edge(&self.bottom_left, &self.bottom, &self.bottom_right, width)
     }
 }
+
+/// Draw a horizontal border of the given width: the corners with the fill repeated in between.
+/// If the fill is wider than one column, the gap it leaves is padded with spaces.
+fn edge(left: &str, fill: &str, right: &str, width: usize) -> String {
+    let inner = width.saturating_sub(visible_width(left) + visible_width(right));
+    let fill_width = visible_width(fill).max(1);
+    let padding = " ".repeat(inner % fill_width);
+    format!(
+        "{}{}{padding}{}",
+        style(left).dark_grey(),
+        style(fill.repeat(inner / fill_width)).dark_grey(),
+        style(right).dark_grey()
+    )
+}
@@ -0,0 +1,125 @@
+/// A command entered in the command-line's command mode
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Change one or more options (e.g. `set number`, `set noborders`, `set tabstop=4`)
+    Set(Vec<Setting>),
+    /// Write the lines to the given file (e.g. `write out.log`)
+    Write(String),
+    /// Filter the lines with the given pattern (e.g. `filter foo`)
+    Filter(String),
+    /// Show the next input file
+    Next,
+    /// Show the previous input file
+    Prev,
+    /// Quit the pager
+    Quit,
+}
+
+/// How a `set` command changes an option
+#[derive(Debug, PartialEq, Eq)]
+pub enum Setting {
+    /// `set name`
+    On(String),
+    /// `set noname`
+    Off(String),
+    /// `set name!` or `set invname`
+    Toggle(String),
+    /// `set name=value`
+    Value(String, String),
+}
+
+impl Setting {
+    /// The name of the option being set
+    pub fn name(&self) -> &str {
+        match self {
+            Setting::On(name) | Setting::Off(name) | Setting::Toggle(name) => name,
+            Setting::Value(name, _) => name,
+        }
+    }
+
+    /// The new value of a switch that is currently `current`
+    pub fn switch(&self, current: bool) -> Result<bool, String> {
+        match self {
+            Setting::On(_) => Ok(true),
+            Setting::Off(_) => Ok(false),
+            Setting::Toggle(_) => Ok(!current),
+            Setting::Value(name, _) => Err(format!("Option {name} does not take a value")),
+        }
+    }
+}
+
+/// Parse the input of the command mode into a [Command]
+pub fn parse_command(input: &str) -> Result<Command, String> {
+    let input = input.trim();
+    let (name, args) = input.split_once(' ').unwrap_or((input, ""));
+    let args = args.trim();
+    match name {
+        "set" | "se" if args.is_empty() => Err("Missing option name".into()),
+        "set" | "se" => Ok(Command::Set(
+            args.split_whitespace().map(parse_setting).collect(),
+        )),
+        "write" | "w" if args.is_empty() => Err("Missing file name".into()),
+        "write" | "w" => Ok(Command::Write(args.into())),
+        "filter" => Ok(Command::Filter(args.into())),
+        "next" | "n" => Ok(Command::Next),
+        "prev" | "previous" | "p" => Ok(Command::Prev),
+        "quit" | "q" => Ok(Command::Quit),
+        "" => Err("Missing command".into()),
+        _ => Err(format!("Unknown command: {name}")),
+    }
+}
+
+/// Parse a single option of a `set` command
+fn parse_setting(arg: &str) -> Setting {
+    if let Some((name, value)) = arg.split_once('=') {
+        Setting::Value(name.into(), value.into())
+    } else if let Some(name) = arg.strip_suffix('!').or(arg.strip_prefix("inv")) {
+        Setting::Toggle(name.into())
+    } else if let Some(name) = arg.strip_prefix("no") {
+        Setting::Off(name.into())
+    } else {
+        Setting::On(arg.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_settings() {
+        assert_eq!(
+            Ok(Command::Set(vec![
+                Setting::On("number".into()),
+                Setting::Off("borders".into()),
+                Setting::Toggle("wrap".into()),
+                Setting::Toggle("number".into()),
+                Setting::Value("tabstop".into(), "4".into()),
+            ])),
+            parse_command("set number noborders wrap! invnumber tabstop=4")
+        );
+    }
+
+    #[test]
+    fn should_parse_commands_with_arguments() {
+        assert_eq!(
+            Ok(Command::Write("out.log".into())),
+            parse_command("write out.log")
+        );
+        assert_eq!(
+            Ok(Command::Filter("foo bar".into())),
+            parse_command(" filter foo bar ")
+        );
+        assert_eq!(Ok(Command::Quit), parse_command("q"));
+        assert_eq!(Ok(Command::Next), parse_command("n"));
+        assert_eq!(Ok(Command::Prev), parse_command("prev"));
+    }
+
+    #[test]
+    fn should_reject_invalid_commands() {
+        assert!(parse_command("").is_err());
+        assert!(parse_command("set").is_err());
+        assert!(parse_command("write").is_err());
+        assert!(parse_command("frobnicate").is_err());
+    }
+}
@@ -0,0 +1,230 @@
+use std::io::Read;
+
+/// Checks the bytes that follow the magic ones, up to the number it takes to recognise the format
+type Validator = fn(&[u8]) -> bool;
+
+/// The magic bytes that start each compression format, along with the number of bytes it takes to recognise it,
+/// the format, and the check on the bytes after the magic ones
+const MAGIC: [(&[u8], usize, Compression, Validator); 4] = [
+    (b"\x1f\x8b", 2, Compression::Gzip, |_| true),
+    // Followed by the block size, from 1 to 9
+    (b"BZh", 4, Compression::Bzip2, |start| {
+        (b'1'..=b'9').contains(&start[3])
+    }),
+    (b"\xfd7zXZ\x00", 6, Compression::Xz, |_| true),
+    (b"\x28\xb5\x2f\xfd", 4, Compression::Zstd, |_| true),
+];
+
+/// The size of the first read of the input, which it is told apart by
+const FIRST_READ_SIZE: usize = 8 * 1024;
+
+/// A compression format, recognised by the magic bytes at the start of the input
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Compression {
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    /// Determine the compression format from the first bytes of the input, if it is compressed
+    pub fn detect(start: &[u8]) -> Option<Self> {
+        MAGIC
+            .iter()
+            .find(|&&(magic, len, _, valid)| {
+                start.len() >= len && start.starts_with(magic) && valid(start)
+            })
+            .map(|&(_, _, compression, _)| compression)
+    }
+
+    /// Returns true if the bytes are too few to tell, as they could be the start of the magic bytes of a format
+    fn is_partial(start: &[u8]) -> bool {
+        MAGIC.iter().any(|&(magic, len, _, _)| {
+            start.len() < len && magic.starts_with(&start[..start.len().min(magic.len())])
+        })
+    }
+
+    /// Wrap the compressed source in a reader that decompresses it as it goes
+    pub fn decoder<R>(self, source: R) -> std::io::Result<Box<dyn Read + Send>>
+    where
+        R: Read + Send + 'static,
+    {
+        Ok(match self {
+            // Concatenated members and streams are read one after the other, like `zcat` does
+            Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(source)),
+            Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(source)),
+            Compression::Xz => Box::new(lzma_rust2::XzReader::new(source, true)),
+            Compression::Zstd => Box::new(
+                ruzstd::decoding::StreamingDecoder::new(source).map_err(std::io::Error::other)?,
+            ),
+        })
+    }
+}
+
+/// Reads the input as it is, or decompressed if it starts with the magic bytes of a compression format.
+/// The input is only looked at on the first read, so that creating the reader never has to wait for it.
+pub struct Decompress<R> {
+    /// The input, until it has been looked at
+    source: Option<R>,
+    /// Reads the input, through a decoder if it is compressed
+    reader: Option<Box<dyn Read + Send>>,
+}
+
+impl<R> Decompress<R>
+where
+    R: Read + Send + 'static,
+{
+    pub fn new(source: R) -> Self {
+        Self {
+            source: Some(source),
+            reader: None,
+        }
+    }
+
+    /// Look at the start of the input and pick the reader for the rest of it.
+    /// It is told apart by what the first read brings in, as more of it may be a while coming (e.g. from a pipe).
+    /// Only a start that is too short to tell has to wait for more.
+    fn detect(mut source: R) -> std::io::Result<Box<dyn Read + Send>> {
+        let mut start = vec![0; FIRST_READ_SIZE];
+        let mut len = 0;
+        loop {
+            let n = match source.read(&mut start[len..]) {
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                result => result?,
+            };
+            len += n;
+            if n == 0 || !Compression::is_partial(&start[..len]) {
+                break;
+            }
+        }
+        start.truncate(len);
+        let compression = Compression::detect(&start);
+        // Put the bytes that were looked at back in front of the rest of the input
+        let source = std::io::Cursor::new(start).chain(source);
+        match compression {
+            Some(compression) => compression.decoder(source),
+            None => Ok(Box::new(source)),
+        }
+    }
+}
+
+impl<R> Read for Decompress<R>
+where
+    R: Read + Send + 'static,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if let Some(source) = self.source.take() {
+            self.reader = Some(Self::detect(source)?);
+        }
+        match &mut self.reader {
+            Some(reader) => reader.read(buf),
+            None => Ok(0),
+        }
+    }
+}
+
+/// Determine the compression format of the file, if it is compressed
+pub fn detect_compression(path: &std::path::Path) -> std::io::Result<Option<Compression>> {
+    let len = MAGIC.iter().map(|m| m.1).max().unwrap_or_default();
+    let mut magic = Vec::with_capacity(len);
+    std::fs::File::open(path)?
+        .take(len as u64)
+        .read_to_end(&mut magic)?;
+    Ok(Compression::detect(&magic))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn decompress(bytes: Vec<u8>) -> String {
+        let mut text = String::new();
+        Decompress::new(std::io::Cursor::new(bytes))
+            .read_to_string(&mut text)
+            .unwrap();
+        text
+    }
+
+    #[test]
+    fn should_decompress_gzip_and_bzip2() {
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        gzip.write_all(b"hello gzip\n").unwrap();
+        assert_eq!("hello gzip\n", decompress(gzip.finish().unwrap()));
+
+        let mut bzip2 = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::fast());
+        bzip2.write_all(b"hello bzip2\n").unwrap();
+        assert_eq!("hello bzip2\n", decompress(bzip2.finish().unwrap()));
+    }
+
+    #[test]
+    fn should_decompress_xz_and_zstd() {
+        // The output of `printf 'hello xz\n' | xz` and `printf 'hello zstd\n' | zstd`
+        let xz = b"\xfd\x37\x7a\x58\x5a\x00\x00\x04\xe6\xd6\xb4\x46\x04\xc0\x0d\x09\x21\x01\x16\x00\
+            \x00\x00\x00\x00\x00\x00\x00\x00\x5f\x4f\x33\xe4\x01\x00\x08\x68\x65\x6c\x6c\x6f\x20\x78\
+            \x7a\x0a\x00\x00\x00\x00\xc1\x49\x3a\xfa\x63\x52\x14\x5a\x00\x01\x29\x09\x64\x92\x1c\x1d\
+            \x1f\xb6\xf3\x7d\x01\x00\x00\x00\x00\x04\x59\x5a";
+        assert_eq!("hello xz\n", decompress(xz.to_vec()));
+
+        let zstd =
+            b"\x28\xb5\x2f\xfd\x04\x58\x59\x00\x00\x68\x65\x6c\x6c\x6f\x20\x7a\x73\x74\x64\x0a\
+            \x6c\x57\xf9\x51";
+        assert_eq!("hello zstd\n", decompress(zstd.to_vec()));
+    }
+
+    #[test]
+    fn should_pass_through_uncompressed_input() {
+        assert_eq!("hi", decompress(b"hi".to_vec()));
+        assert_eq!("plain text\n", decompress(b"plain text\n".to_vec()));
+        assert_eq!("BZhello", decompress(b"BZhello".to_vec()));
+    }
+
+    #[test]
+    fn should_tell_from_the_first_read_without_waiting_for_more() {
+        let (pipe, mut writer) = std::io::pipe().unwrap();
+        writer.write_all(b"hi").unwrap();
+        let mut buf = [0; 16];
+        let n = Decompress::new(pipe).read(&mut buf).unwrap();
+        assert_eq!(b"hi", &buf[..n]);
+        drop(writer);
+
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        gzip.write_all(b"split gzip\n").unwrap();
+        let gzip = gzip.finish().unwrap();
+        let (pipe, mut writer) = std::io::pipe().unwrap();
+        writer.write_all(&gzip[..1]).unwrap();
+        let rest = std::thread::spawn(move || writer.write_all(&gzip[1..]));
+        let mut text = String::new();
+        Decompress::new(pipe).read_to_string(&mut text).unwrap();
+        rest.join().unwrap().unwrap();
+        assert_eq!("split gzip\n", text);
+    }
+
+    #[test]
+    fn should_detect_the_format_from_the_magic_bytes() {
+        assert_eq!(
+            Some(Compression::Gzip),
+            Compression::detect(b"\x1f\x8b\x08")
+        );
+        assert_eq!(Some(Compression::Bzip2), Compression::detect(b"BZh9"));
+        assert_eq!(None, Compression::detect(b"BZh!"));
+        assert_eq!(None, Compression::detect(b"BZh"));
+        assert_eq!(Some(Compression::Xz), Compression::detect(b"\xfd7zXZ\x00"));
+        assert_eq!(None, Compression::detect(b"\xfd7zXZ"));
+        assert_eq!(
+            Some(Compression::Zstd),
+            Compression::detect(b"\x28\xb5\x2f\xfd")
+        );
+    }
+
+    #[test]
+    fn should_only_wait_for_the_start_of_magic_bytes() {
+        assert!(Compression::is_partial(b"\x1f"));
+        assert!(Compression::is_partial(b"BZh"));
+        assert!(Compression::is_partial(b"\xfd7zX"));
+        assert!(!Compression::is_partial(b"\x1f\x8b"));
+        assert!(!Compression::is_partial(b"BZ!"));
+        assert!(!Compression::is_partial(b"h"));
+    }
+}
@@ -0,0 +1,75 @@
+/// Clamps the byte offset `i` to the text, moving it back onto a character boundary if needed
+pub fn clamp_cursor(text: &str, i: usize) -> usize {
+    let mut i = i.min(text.len());
+    while !text.is_char_boundary(i) {
+        i -= 1;
+    }
+    i
+}
+
+/// The position of the character before the cursor
+pub fn prev_char(text: &str, cursor: usize) -> usize {
+    text[..cursor]
+        .char_indices()
+        .next_back()
+        .map_or(0, |(i, _)| i)
+}
+
+/// The position after the character under the cursor
+pub fn next_char(text: &str, cursor: usize) -> usize {
+    text[cursor..]
+        .chars()
+        .next()
+        .map_or(cursor, |c| cursor + c.len_utf8())
+}
+
+/// The position of the start of the word before the cursor
+pub fn prev_word(text: &str, cursor: usize) -> usize {
+    let before = text[..cursor].trim_end();
+    before
+        .char_indices()
+        .rev()
+        .find(|(_, c)| c.is_whitespace())
+        .map_or(0, |(i, c)| i + c.len_utf8())
+}
+
+/// The position of the end of the word after the cursor
+pub fn next_word(text: &str, cursor: usize) -> usize {
+    let after = &text[cursor..];
+    let start = after.len() - after.trim_start().len();
+    after[start..]
+        .find(char::is_whitespace)
+        .map_or(text.len(), |i| cursor + start + i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_move_by_characters() {
+        let text = "añb";
+        assert_eq!(1, next_char(text, 0));
+        assert_eq!(3, next_char(text, 1));
+        assert_eq!(4, next_char(text, 4));
+        assert_eq!(1, prev_char(text, 3));
+        assert_eq!(0, prev_char(text, 0));
+    }
+
+    #[test]
+    fn should_move_by_words() {
+        let text = "one  two three";
+        assert_eq!(5, prev_word(text, 8));
+        assert_eq!(5, prev_word(text, 7));
+        assert_eq!(0, prev_word(text, 5));
+        assert_eq!(3, next_word(text, 0));
+        assert_eq!(8, next_word(text, 3));
+        assert_eq!(14, next_word(text, 8));
+    }
+
+    #[test]
+    fn should_clamp_the_cursor_onto_the_text() {
+        assert_eq!(3, clamp_cursor("abc", 10));
+        assert_eq!(1, clamp_cursor("añ", 2));
+    }
+}
@@ -0,0 +1,197 @@
+use std::str::FromStr;
+
+/// The byte order marks that tell the encoding of the input
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const UTF16LE_BOM: &[u8] = b"\xFF\xFE";
+const UTF16BE_BOM: &[u8] = b"\xFE\xFF";
+
+/// The character encoding of the input
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Encoding {
+    /// UTF-8, unless a byte order mark says it is UTF-16
+    #[default]
+    Auto,
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// ISO-8859-1, where every byte is a character of its own
+    Latin1,
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('_', "-").as_str() {
+            "auto" => Ok(Encoding::Auto),
+            "utf-8" | "utf8" => Ok(Encoding::Utf8),
+            "utf-16le" | "utf16le" => Ok(Encoding::Utf16Le),
+            "utf-16be" | "utf16be" => Ok(Encoding::Utf16Be),
+            "latin1" | "latin-1" | "iso-8859-1" => Ok(Encoding::Latin1),
+            _ => Err(format!(
+                "Unknown encoding: {s} (expected auto, utf-8, utf-16le, utf-16be or latin1)"
+            )),
+        }
+    }
+}
+
+impl Encoding {
+    /// The length of the byte order mark left out at the start of the input (starting with the given bytes),
+    /// if the rest of it is let through as it is. Returns None if the input is converted.
+    pub fn passthrough(self, start: &[u8]) -> Option<usize> {
+        match self {
+            Encoding::Utf8 => Some(0),
+            Encoding::Auto if start.starts_with(UTF16LE_BOM) || start.starts_with(UTF16BE_BOM) => {
+                None
+            }
+            Encoding::Auto if start.starts_with(UTF8_BOM) => Some(UTF8_BOM.len()),
+            Encoding::Auto => Some(0),
+            _ => None,
+        }
+    }
+}
+
+/// Converts the input to UTF-8, one chunk at a time.
+/// Invalid UTF-8 is let through as it is, to be replaced when the lines are read.
+pub struct Decoder {
+    encoding: Encoding,
+    /// The bytes at the end of the last chunk that are only a part of a character
+    pending: Vec<u8>,
+}
+
+impl Decoder {
+    pub fn new(encoding: Encoding) -> Self {
+        Self {
+            encoding,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Returns true if the input is converted from another encoding, rather than let through as it is.
+    /// Only known once the byte order mark has been looked for.
+    pub fn converts(&self) -> bool {
+        matches!(
+            self.encoding,
+            Encoding::Latin1 | Encoding::Utf16Le | Encoding::Utf16Be
+        )
+    }
+
+    /// Convert the next chunk of the input to UTF-8
+    pub fn decode(&mut self, chunk: &[u8]) -> Vec<u8> {
+        let mut bytes = std::mem::take(&mut self.pending);
+        bytes.extend_from_slice(chunk);
+
+        // Work out the encoding from the byte order mark at the start of the input
+        if self.encoding == Encoding::Auto {
+            if bytes.len() < UTF8_BOM.len() && UTF8_BOM.starts_with(&bytes) {
+                self.pending = bytes; // Wait for enough of the input to tell
+                return Vec::new();
+            }
+            self.encoding = if bytes.starts_with(UTF16LE_BOM) {
+                Encoding::Utf16Le
+            } else if bytes.starts_with(UTF16BE_BOM) {
+                Encoding::Utf16Be
+            } else {
+                Encoding::Utf8
+            };
+            if let Some(rest) = [UTF8_BOM, UTF16LE_BOM, UTF16BE_BOM]
+                .iter()
+                .find_map(|bom| bytes.strip_prefix(*bom))
+            {
+                bytes = rest.to_vec();
+            }
+        }
+
+        match self.encoding {
+            Encoding::Auto | Encoding::Utf8 => bytes,
+            Encoding::Latin1 => bytes
+                .iter()
+                .map(|&b| char::from(b))
+                .collect::<String>()
+                .into_bytes(),
+            Encoding::Utf16Le => self.decode_utf16(bytes, u16::from_le_bytes),
+            Encoding::Utf16Be => self.decode_utf16(bytes, u16::from_be_bytes),
+        }
+    }
+
+    /// The bytes left over at the end of the input, which don't make up a whole character
+    pub fn finish(&mut self) -> Vec<u8> {
+        let rest = std::mem::take(&mut self.pending);
+        match self.encoding {
+            Encoding::Utf16Le | Encoding::Utf16Be if !rest.is_empty() => {
+                char::REPLACEMENT_CHARACTER.to_string().into_bytes()
+            }
+            _ => rest,
+        }
+    }
+
+    /// Decode the UTF-16 code units, keeping back an odd byte or a surrogate that is missing its pair
+    fn decode_utf16(&mut self, mut bytes: Vec<u8>, unit: fn([u8; 2]) -> u16) -> Vec<u8> {
+        let mut units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| unit([pair[0], pair[1]]))
+            .collect();
+        let mut keep = bytes.len() % 2;
+        if units.last().is_some_and(|u| (0xD800..0xDC00).contains(u)) {
+            units.pop();
+            keep += 2;
+        }
+        self.pending = bytes.split_off(bytes.len() - keep);
+        char::decode_utf16(units)
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect::<String>()
+            .into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(encoding: Encoding, chunks: &[&[u8]]) -> String {
+        let mut decoder = Decoder::new(encoding);
+        let bytes: Vec<u8> = chunks.iter().flat_map(|c| decoder.decode(c)).collect();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn should_detect_utf16_from_the_byte_order_mark() {
+        assert_eq!("hi\n", decode(Encoding::Auto, &[b"\xFF\xFEh\0i\0\n\0"]));
+        assert_eq!("hi\n", decode(Encoding::Auto, &[b"\xFE\xFF\0h\0i\0\n"]));
+        assert_eq!("hi", decode(Encoding::Auto, &[b"\xEF", b"\xBB\xBFhi"]));
+    }
+
+    #[test]
+    fn should_join_characters_split_across_chunks() {
+        // U+1F980 (crab) is a surrogate pair in UTF-16
+        let crab = b"\x3E\xD8\x80\xDD";
+        assert_eq!(
+            "🦀a",
+            decode(
+                Encoding::Utf16Le,
+                &[&crab[..1], &crab[1..3], &crab[3..], b"a\0"]
+            )
+        );
+    }
+
+    #[test]
+    fn should_decode_latin1() {
+        assert_eq!("café", decode(Encoding::Latin1, &[b"caf\xE9"]));
+    }
+
+    #[test]
+    fn should_tell_if_the_input_is_let_through_as_it_is() {
+        assert_eq!(Some(0), Encoding::Auto.passthrough(b"plain"));
+        assert_eq!(Some(3), Encoding::Auto.passthrough(b"\xEF\xBB\xBFbom"));
+        assert_eq!(None, Encoding::Auto.passthrough(b"\xFF\xFEh\0"));
+        assert_eq!(Some(0), Encoding::Utf8.passthrough(b"\xFF\xFEh\0"));
+        assert_eq!(None, Encoding::Latin1.passthrough(b"plain"));
+    }
+
+    #[test]
+    fn should_parse_encoding_names() {
+        assert_eq!(Ok(Encoding::Utf16Le), "UTF-16LE".parse());
+        assert_eq!(Ok(Encoding::Latin1), "iso_8859_1".parse());
+        assert!("ebcdic".parse::<Encoding>().is_err());
+    }
+}
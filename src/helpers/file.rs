@@ -2,7 +2,7 @@ use std::str::FromStr;
 
 #[derive(Clone)]
 pub struct File {
-    filename: String,
+    pub filename: String,
     pub row: Option<usize>,
     pub col: Option<usize>,
 }
@@ -19,24 +19,68 @@ impl FromStr for File {
     }
 }
 
-/// Returns a BufReader. If a filepath is specified, returns a BufReader for the File,
-/// otherwise, returns a BufReader for STDIN.
+/// Returns a Reader. If a filepath is specified, returns a Reader for the File,
+/// otherwise, returns a Reader for STDIN. Files are run through the preprocessor, if there is one.
+/// Compressed contents are decompressed, and the contents are converted from the encoding to UTF-8.
 pub fn get_reader(
-    file: &Option<File>,
-) -> Result<Box<dyn std::io::BufRead>, Box<dyn std::error::Error>> {
-    let reader: Box<dyn std::io::BufRead> = if let Some(file) = &file {
-        let filepath = std::path::Path::new(&file.filename);
-        if !filepath.exists() {
-            return Err(format!("The provided file does not exist: {}", file.filename).into());
+    file: Option<&File>,
+    encoding: super::Encoding,
+    preprocessor: Option<&super::Preprocessor>,
+) -> Result<super::Reader, Box<dyn std::error::Error>> {
+    let reader = if let Some(file) = file {
+        let filepath = existing(file)?;
+        let preprocessed = match preprocessor {
+            Some(preprocessor) => preprocessor
+                .run(filepath)
+                .map_err(|e| format!("Could not preprocess {}: {e}", file.filename))?,
+            None => None,
+        };
+        let filepath = match preprocessed {
+            Some(super::Preprocessed::Output(output)) => {
+                return Ok(super::Reader::stream(
+                    super::Decompress::new(output),
+                    encoding,
+                ));
+            }
+            Some(super::Preprocessed::File(path)) => path,
+            None => filepath.to_path_buf(),
+        };
+        let filepath = filepath.as_path();
+        // A compressed file won't grow, so it doesn't need to be followed
+        match super::detect_compression(filepath)? {
+            Some(compression) => {
+                let file = std::fs::File::open(filepath)?;
+                super::Reader::stream(compression.decoder(file)?, encoding)
+            }
+            None => super::Reader::file(filepath, encoding)?,
         }
-        let file = std::fs::File::open(filepath)?;
-        Box::new(std::io::BufReader::new(file))
     } else {
-        Box::new(std::io::stdin().lock())
+        super::Reader::stream(super::Decompress::new(std::io::stdin()), encoding)
     };
     Ok(reader)
 }
 
+/// Returns the contents of the file (or of STDIN) to pass through as they are, without paging them.
+/// Like `less`, files aren't run through the preprocessor, compressed contents are not decompressed
+/// and the contents are not converted from their encoding.
+pub fn get_source(
+    file: Option<&File>,
+) -> Result<Box<dyn std::io::Read>, Box<dyn std::error::Error>> {
+    match file {
+        Some(file) => Ok(Box::new(std::fs::File::open(existing(file)?)?)),
+        None => Ok(Box::new(std::io::stdin())),
+    }
+}
+
+/// The path of the file, if it exists
+fn existing(file: &File) -> Result<&std::path::Path, Box<dyn std::error::Error>> {
+    let filepath = std::path::Path::new(&file.filename);
+    if !filepath.exists() {
+        return Err(format!("The provided file does not exist: {}", file.filename).into());
+    }
+    Ok(filepath)
+}
+
 /// Parses a string line:col string into a tuple of numbers representing the row and col
 pub fn parse_row_and_col(s: &str) -> (Option<usize>, Option<usize>) {
     let mut iter = s.split(":");
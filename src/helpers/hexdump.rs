@@ -0,0 +1,81 @@
+/// The number of bytes shown on each row of a hex dump
+pub const BYTES_PER_ROW: usize = 16;
+
+/// Returns true if the block of the input looks like binary data rather than text (it has NUL bytes)
+pub fn is_binary(block: &[u8]) -> bool {
+    block.contains(&0)
+}
+
+/// Format a row of a hex dump: the offset, the bytes in hex and the bytes as ASCII.
+/// e.g. `00000000  48 65 6c 6c 6f 0a 00 ff                           |Hello...|`
+pub fn hexdump_row(offset: usize, bytes: &[u8]) -> String {
+    let mut row = format!("{offset:08x} ");
+    for i in 0..BYTES_PER_ROW {
+        // Split the bytes into two groups of eight
+        if i % 8 == 0 {
+            row.push(' ');
+        }
+        match bytes.get(i) {
+            Some(b) => row.push_str(&format!("{b:02x} ")),
+            None => row.push_str("   "),
+        }
+    }
+    let ascii: String = bytes
+        .iter()
+        .map(|&b| match b {
+            0x20..=0x7E => char::from(b),
+            _ => '.',
+        })
+        .collect();
+    row.push_str(&format!(" |{ascii}|"));
+    row
+}
+
+/// Parse a byte offset given in hex, like `0x1f00`
+pub fn parse_offset(s: &str) -> Option<usize> {
+    let hex = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X"))?;
+    usize::from_str_radix(hex, 16).ok()
+}
+
+/// Returns true if the goto input is (or is on its way to being) a line number with an optional column,
+/// like `12:3`, or a hex offset, like `0x1f00`. Hex digits only make sense after the `0x`.
+pub fn is_goto_input(s: &str) -> bool {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => hex.chars().all(|c| c.is_ascii_hexdigit()),
+        None => s.chars().all(|c| c.is_ascii_digit() || c == ':'),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_format_a_row_of_the_hex_dump() {
+        assert_eq!(
+            "00001f00  48 65 6c 6c 6f 0a 00 ff  41 42 43 44 45 46 47 48  |Hello...ABCDEFGH|",
+            hexdump_row(0x1f00, b"Hello\n\0\xffABCDEFGH")
+        );
+        assert_eq!(
+            "00000010  41                                                |A|",
+            hexdump_row(0x10, b"A")
+        );
+    }
+
+    #[test]
+    fn should_parse_hex_offsets() {
+        assert_eq!(Some(0x1f00), parse_offset("0x1f00"));
+        assert_eq!(None, parse_offset("1f00"));
+        assert_eq!(None, parse_offset("0xzz"));
+    }
+
+    #[test]
+    fn should_only_take_hex_digits_after_0x_in_the_goto_input() {
+        assert!(is_goto_input("0x1F00"));
+        assert!(is_goto_input("0X"));
+        assert!(is_goto_input("12:3"));
+        assert!(!is_goto_input("abc"));
+        assert!(!is_goto_input("1f:2"));
+        assert!(!is_goto_input("10x1f"));
+    }
+}
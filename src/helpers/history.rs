@@ -0,0 +1,210 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// The maximum number of entries remembered by a history
+const MAX_ENTRIES: usize = 100;
+
+/// The previously submitted inputs of a command-line prompt, that can be recalled with Up and Down
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct History {
+    /// The entries, from oldest to newest
+    entries: Vec<String>,
+    /// The index of the entry being recalled, if any
+    cursor: Option<usize>,
+    /// The input the user was typing before they started recalling entries
+    draft: String,
+    /// The entries added since the history was last saved, from oldest to newest
+    unsaved: Vec<String>,
+}
+
+impl History {
+    /// Add an entry to the history. Moves it to the front if it was already there.
+    pub fn push(&mut self, entry: &str) {
+        self.cursor = None;
+        if entry.is_empty() || entry.contains('\n') {
+            return;
+        }
+        self.entries.retain(|e| e != entry);
+        self.entries.push(entry.to_string());
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        self.unsaved.push(entry.to_string());
+    }
+
+    /// The given history (as it was saved by other sessions) with the entries that haven't been saved yet added to it
+    fn merged(&self, mut saved: History) -> History {
+        for entry in &self.unsaved {
+            saved.push(entry);
+        }
+        saved.unsaved.clear();
+        saved
+    }
+
+    /// Recall the entry before the one being recalled.
+    /// If the current input isn't a recalled entry, start again from the newest entry.
+    pub fn prev(&mut self, current: &str) -> Option<String> {
+        let index = match self.cursor {
+            Some(i) if self.entries[i] == current => i.checked_sub(1)?,
+            _ => {
+                let index = self.entries.len().checked_sub(1)?;
+                self.draft = current.to_string();
+                index
+            }
+        };
+        self.cursor = Some(index);
+        Some(self.entries[index].clone())
+    }
+
+    /// Recall the entry after the one being recalled.
+    /// Moving past the newest entry brings back what the user was typing before.
+    pub fn next(&mut self, current: &str) -> Option<String> {
+        match self.cursor {
+            Some(i) if self.entries[i] == current => {
+                if i + 1 < self.entries.len() {
+                    self.cursor = Some(i + 1);
+                    Some(self.entries[i + 1].clone())
+                } else {
+                    self.cursor = None;
+                    Some(std::mem::take(&mut self.draft))
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The path to the history file: `$XDG_STATE_HOME/scan/history`, or `~/.local/state/scan/history`
+pub fn history_path() -> Option<PathBuf> {
+    let state = match std::env::var_os("XDG_STATE_HOME").filter(|s| !s.is_empty()) {
+        Some(state) => PathBuf::from(state),
+        None => PathBuf::from(std::env::var_os("HOME")?)
+            .join(".local")
+            .join("state"),
+    };
+    Some(state.join("scan").join("history"))
+}
+
+/// Load the search and goto histories from the history file.
+/// Each line holds an entry, prefixed by `/` for searches or `:` for gotos.
+pub fn load_history(path: &Path) -> std::io::Result<(History, History)> {
+    let (mut search, mut goto) = (History::default(), History::default());
+    for line in std::fs::read_to_string(path)?.lines() {
+        if let Some(entry) = line.strip_prefix('/') {
+            search.push(entry);
+        } else if let Some(entry) = line.strip_prefix(':') {
+            goto.push(entry);
+        }
+    }
+    search.unsaved.clear();
+    goto.unsaved.clear();
+    Ok((search, goto))
+}
+
+/// Save the search and goto histories to the history file.
+/// The entries added since they were last saved are added to those in the file, so that the entries
+/// saved by other sessions in the meantime are kept. The file is replaced in one go, rather than written over.
+pub fn save_history(path: &Path, search: &mut History, goto: &mut History) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    // A missing or unreadable history file is replaced
+    let (saved_search, saved_goto) = load_history(path).unwrap_or_default();
+    let merged_search = search.merged(saved_search);
+    let merged_goto = goto.merged(saved_goto);
+
+    let mut contents = String::new();
+    for entry in &merged_search.entries {
+        contents.push_str(&format!("/{entry}\n"));
+    }
+    for entry in &merged_goto.entries {
+        contents.push_str(&format!(":{entry}\n"));
+    }
+    let temp = path.with_extension(format!("{}.tmp", std::process::id()));
+    let written = std::fs::File::create(&temp).and_then(|mut file| {
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()
+    });
+    match written.and_then(|()| std::fs::rename(&temp, path)) {
+        Ok(()) => {
+            *search = merged_search;
+            *goto = merged_goto;
+            Ok(())
+        }
+        Err(e) => {
+            let _ = std::fs::remove_file(&temp);
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(entries: &[&str]) -> History {
+        let mut history = History::default();
+        for entry in entries {
+            history.push(entry);
+        }
+        history
+    }
+
+    #[test]
+    fn should_recall_entries_from_newest_to_oldest() {
+        let mut history = history(&["one", "two", "three"]);
+        assert_eq!(Some("three".into()), history.prev("draft"));
+        assert_eq!(Some("two".into()), history.prev("three"));
+        assert_eq!(Some("one".into()), history.prev("two"));
+        assert_eq!(None, history.prev("one"));
+    }
+
+    #[test]
+    fn should_bring_back_the_draft_after_the_newest_entry() {
+        let mut history = history(&["one", "two"]);
+        history.prev("draft");
+        history.prev("two");
+        assert_eq!(Some("two".into()), history.next("one"));
+        assert_eq!(Some("draft".into()), history.next("two"));
+        assert_eq!(None, history.next("draft"));
+    }
+
+    #[test]
+    fn should_move_repeated_entries_to_the_front() {
+        let mut history = history(&["one", "two", "one"]);
+        assert_eq!(Some("one".into()), history.prev(""));
+        assert_eq!(Some("two".into()), history.prev("one"));
+        assert_eq!(None, history.prev("two"));
+    }
+
+    #[test]
+    fn should_save_and_load_the_histories() {
+        let path = std::env::temp_dir()
+            .join(format!("scan-test-{}", std::process::id()))
+            .join("history");
+        let mut search = history(&["ERROR|WARN", "timeout"]);
+        let mut goto = history(&["42", "7:3"]);
+        save_history(&path, &mut search, &mut goto).unwrap();
+        let loaded = load_history(&path).unwrap();
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert_eq!((search, goto), loaded);
+    }
+
+    #[test]
+    fn should_keep_the_entries_saved_by_other_sessions() {
+        let path = std::env::temp_dir()
+            .join(format!("scan-test-merge-{}", std::process::id()))
+            .join("history");
+        let (mut search, mut goto) = (history(&["mine"]), History::default());
+        let (mut other, mut other_goto) = (history(&["theirs", "both"]), history(&["9"]));
+        save_history(&path, &mut other, &mut other_goto).unwrap();
+
+        search.push("both");
+        save_history(&path, &mut search, &mut goto).unwrap();
+        let (loaded, loaded_goto) = load_history(&path).unwrap();
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert_eq!(history(&["theirs", "mine", "both"]).entries, loaded.entries);
+        assert_eq!(history(&["9"]).entries, loaded_goto.entries);
+        assert_eq!(loaded, search);
+    }
+}
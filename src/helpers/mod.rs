@@ -1,11 +1,33 @@
 mod ansi;
+mod backing;
 mod borders;
+mod command;
+mod decompress;
+mod editing;
+mod encoding;
 mod file;
+mod hexdump;
+mod history;
 pub mod layout;
+mod preprocess;
+mod reader;
+mod search;
+mod sgr;
 
 pub use ansi::*;
+pub use backing::*;
 pub use borders::*;
+pub use command::*;
+pub use decompress::*;
+pub use editing::*;
+pub use encoding::*;
 pub use file::*;
+pub use hexdump::*;
+pub use history::*;
+pub use preprocess::*;
+pub use reader::*;
+pub use search::*;
+pub use sgr::*;
 
 use crossterm::{
     style::{style, Stylize},
@@ -0,0 +1,199 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdout, Command, Stdio};
+
+/// The environment variable with the command that preprocesses the input files (like `LESSOPEN`)
+const PREPROCESSOR_VAR: &str = "SCANOPEN";
+
+/// A user command that the input files are run through before they are read, like `LESSOPEN` for `less`.
+/// `%s` in the command is replaced by the path of the file. What the command prints to stderr is discarded,
+/// as it would be printed over the pager.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Preprocessor {
+    /// `|command %s`: read the output of the command instead of the file, unless it has none
+    Pipe(String),
+    /// `command %s`: the command prints the path of a file to read instead, if there is one.
+    /// Unlike `LESSCLOSE`, there is no command to remove the file afterwards, so temporary files are left behind
+    Replace(String),
+}
+
+/// What to read instead of the input file
+pub enum Preprocessed {
+    /// The output of the command
+    Output(Box<dyn Read + Send>),
+    /// Another file
+    File(PathBuf),
+}
+
+/// The output of a piped command, which is waited on once it is no longer read
+struct Output {
+    child: Child,
+    stdout: std::io::Chain<std::io::Cursor<Vec<u8>>, ChildStdout>,
+}
+
+impl Read for Output {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.stdout.read(buf)
+    }
+}
+
+impl Drop for Output {
+    /// Stop the command if it is still running when the output is dropped, and don't leave it behind as a zombie
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Preprocessor {
+    /// The preprocessor set in the environment, if any
+    pub fn from_env() -> Option<Self> {
+        Self::parse(&std::env::var(PREPROCESSOR_VAR).ok()?)
+    }
+
+    /// Parse the preprocessor from its command, which is piped if it starts with `|`
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        match s.strip_prefix('|') {
+            Some(command) if !command.trim().is_empty() => {
+                Some(Preprocessor::Pipe(command.trim().into()))
+            }
+            Some(_) => None,
+            None if s.is_empty() => None,
+            None => Some(Preprocessor::Replace(s.into())),
+        }
+    }
+
+    /// Run the preprocessor on the file. Returns what to read instead of the file, if anything
+    pub fn run(&self, path: &Path) -> std::io::Result<Option<Preprocessed>> {
+        match self {
+            Preprocessor::Pipe(command) => {
+                let mut child = shell(&expand(command, path))
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::null())
+                    .spawn()?;
+                let Some(mut stdout) = child.stdout.take() else {
+                    return Ok(None);
+                };
+                // Wait for the first of the output to tell if there is any
+                let mut first = vec![0; 8 * 1024];
+                let n = loop {
+                    match stdout.read(&mut first) {
+                        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                        result => break result?,
+                    }
+                };
+                if n == 0 {
+                    child.wait()?;
+                    return Ok(None);
+                }
+                first.truncate(n);
+                let stdout = std::io::Cursor::new(first).chain(stdout);
+                Ok(Some(Preprocessed::Output(Box::new(Output {
+                    child,
+                    stdout,
+                }))))
+            }
+            Preprocessor::Replace(command) => {
+                let output = shell(&expand(command, path))
+                    .stderr(Stdio::null())
+                    .output()?;
+                let replacement = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                Ok((!replacement.is_empty()).then(|| Preprocessed::File(replacement.into())))
+            }
+        }
+    }
+}
+
+/// Put the quoted path in place of `%s` in the command, or at the end if it has no `%s`
+fn expand(command: &str, path: &Path) -> String {
+    let quoted = quote(&path.to_string_lossy());
+    if command.contains("%s") {
+        command.replace("%s", &quoted)
+    } else {
+        format!("{command} {quoted}")
+    }
+}
+
+/// Quote the argument for the shell
+#[cfg(unix)]
+fn quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+#[cfg(not(unix))]
+fn quote(arg: &str) -> String {
+    format!("\"{arg}\"")
+}
+
+/// Run the command line with the shell
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_the_preprocessor() {
+        assert_eq!(
+            Some(Preprocessor::Pipe("lesspipe %s".into())),
+            Preprocessor::parse("| lesspipe %s")
+        );
+        assert_eq!(
+            Some(Preprocessor::Replace("unpack %s".into())),
+            Preprocessor::parse("unpack %s")
+        );
+        assert_eq!(None, Preprocessor::parse("|"));
+        assert_eq!(None, Preprocessor::parse(""));
+    }
+
+    #[test]
+    fn should_quote_the_path_into_the_command() {
+        assert_eq!(
+            "cat 'it'\\''s here.log' | head",
+            expand("cat %s | head", Path::new("it's here.log"))
+        );
+        assert_eq!("jq . 'a.json'", expand("jq .", Path::new("a.json")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn should_read_the_output_of_the_command_unless_there_is_none() {
+        let path = Path::new("some file");
+        let pipe = Preprocessor::parse("|echo preprocessed %s").unwrap();
+        let Some(Preprocessed::Output(mut output)) = pipe.run(path).unwrap() else {
+            panic!("expected the output of the command");
+        };
+        let mut text = String::new();
+        output.read_to_string(&mut text).unwrap();
+        assert_eq!("preprocessed some file\n", text);
+
+        let silent = Preprocessor::parse("|true %s").unwrap();
+        assert!(silent.run(path).unwrap().is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn should_stop_the_command_when_its_output_is_dropped() {
+        let pipe = Preprocessor::parse("|echo oops >&2; yes %s").unwrap();
+        let Some(Preprocessed::Output(mut output)) = pipe.run(Path::new("y")).unwrap() else {
+            panic!("expected the output of the command");
+        };
+        let mut first = [0; 4];
+        output.read_exact(&mut first).unwrap();
+        assert_eq!(b"y\ny\n", &first);
+        drop(output);
+    }
+}
@@ -0,0 +1,691 @@
+use std::io::{BufRead, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::time::Duration;
+
+use super::{Decoder, Encoding};
+
+/// How long to wait before checking a file for new contents again, once its end has been reached
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// The size of the chunks read from the input
+const CHUNK_SIZE: usize = 64 * 1024;
+/// The number of chunks that can be read ahead of the pager
+const READ_AHEAD: usize = 16;
+/// The line inserted into the contents when the file is rotated or truncated
+const ROTATED_MARKER: &[u8] = b"--- file rotated ---\n";
+
+/// What the background thread sends to the [Reader]
+enum Message {
+    /// The next chunk of the input
+    Data(Vec<u8>),
+    /// The end of the input has been reached (for now, if it is a file that may still grow)
+    End,
+    /// Reading the input failed
+    Error(std::io::Error),
+    /// The input was moved to the offset the reader asked for. The chunks after this one are read from there
+    Seeked,
+}
+
+/// A shared switch that keeps the pager looking for new contents at the end of the input (like `tail -f`)
+#[derive(Clone, Default)]
+pub struct Follow(Arc<FollowState>);
+
+#[derive(Default)]
+struct FollowState {
+    on: AtomicBool,
+    /// Counts the times the background thread was woken up, to tell if it was while it was getting ready to wait
+    wakes: Mutex<u64>,
+    woken: Condvar,
+}
+
+impl Follow {
+    /// Turn following on or off
+    pub fn set(&self, yes: bool) {
+        self.0.on.store(yes, Ordering::Relaxed);
+        self.wake();
+    }
+
+    /// Returns true if following is turned on
+    pub fn is_on(&self) -> bool {
+        self.0.on.load(Ordering::Relaxed)
+    }
+
+    /// The number of times the background thread was woken up so far
+    fn wakes(&self) -> u64 {
+        *self.0.wakes.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Wake up the background thread if it is waiting
+    fn wake(&self) {
+        *self.0.wakes.lock().unwrap_or_else(PoisonError::into_inner) += 1;
+        self.0.woken.notify_all();
+    }
+
+    /// Wait until the background thread is woken up, unless it already was since the given number of wakes
+    fn wait(&self, wakes: u64) {
+        let guard = self.0.wakes.lock().unwrap_or_else(PoisonError::into_inner);
+        let _guard = self
+            .0
+            .woken
+            .wait_while(guard, |&mut n| n == wakes)
+            .unwrap_or_else(PoisonError::into_inner);
+    }
+}
+
+/// A shared flag telling whether the input is converted from another encoding,
+/// which is only known once it starts coming in (from its byte order mark)
+#[derive(Clone, Default)]
+pub struct Converted(Arc<AtomicBool>);
+
+impl Converted {
+    /// Returns true if the input is converted, so that its bytes aren't those of the file
+    pub fn is_set(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A handle on the file a [Reader] reads, to read its contents back from any offset.
+/// The offsets are those of the contents as the reader gives them, which only match the file's
+/// as long as they are let through as they are, so a source is only available for such files.
+#[derive(Clone)]
+pub struct Source {
+    file: Arc<std::fs::File>,
+    /// The number of bytes at the start of the file that are left out of the contents (a byte order mark)
+    base: u64,
+    /// The length of the contents that came from the file before it was rotated (the maximum until then)
+    limit: Arc<AtomicU64>,
+    /// Set once the file has been truncated in place, which loses the contents it had
+    truncated: Arc<AtomicBool>,
+}
+
+impl Source {
+    /// Open the file as a source, unless its contents are converted from another encoding
+    fn open(path: &Path, encoding: Encoding) -> std::io::Result<Option<Self>> {
+        let file = std::fs::File::open(path)?;
+        let mut start = Vec::new();
+        (&file).take(3).read_to_end(&mut start)?;
+        Ok(encoding.passthrough(&start).map(|base| Self {
+            file: Arc::new(file),
+            base: base as u64,
+            limit: Arc::new(AtomicU64::new(u64::MAX)),
+            truncated: Arc::new(AtomicBool::new(false)),
+        }))
+    }
+
+    /// The length of the contents that can be read back from the file.
+    /// Once the file has been rotated, the rest of the contents come from another file.
+    pub fn len(&self) -> std::io::Result<u64> {
+        let len = self.file.metadata()?.len().saturating_sub(self.base);
+        Ok(len.min(self.limit()))
+    }
+
+    /// The length of the contents before the file was rotated, or the maximum if it hasn't been
+    pub fn limit(&self) -> u64 {
+        self.limit.load(Ordering::Relaxed)
+    }
+
+    /// Returns true if the file has been rotated, so that the reader can't be moved around in it anymore
+    pub fn is_rotated(&self) -> bool {
+        self.limit() != u64::MAX
+    }
+
+    /// Returns true if the file has been truncated in place, so that the contents before the limit can't be read back
+    pub fn is_truncated(&self) -> bool {
+        self.truncated.load(Ordering::Relaxed)
+    }
+
+    /// Check whether the file has been truncated, against the length of the contents read from it,
+    /// in case the reader hasn't noticed yet. Returns true if it has been.
+    pub fn check_truncated(&self, read: u64) -> bool {
+        if self.is_truncated() {
+            return true;
+        }
+        let read = read.min(self.limit());
+        let len = self
+            .file
+            .metadata()
+            .map_or(u64::MAX, |metadata| metadata.len());
+        if len.saturating_sub(self.base) < read {
+            self.truncated.store(true, Ordering::Relaxed);
+        }
+        self.is_truncated()
+    }
+
+    /// Read the contents at the offset into the buffer. Only comes up short at the end of the file
+    pub fn read_at(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut file = &*self.file;
+        file.seek(SeekFrom::Start(self.base + offset))?;
+        let mut filled = 0;
+        while filled < buf.len() {
+            match file.read(&mut buf[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(filled)
+    }
+}
+
+/// Lets the background thread move around a file that it reads as it is
+struct Seeks {
+    /// The offsets the reader asks to move to
+    requests: Receiver<u64>,
+    /// Where the contents were when the file was rotated, after which they can't be moved around anymore
+    limit: Arc<AtomicU64>,
+    /// Set when the file is truncated in place rather than replaced
+    truncated: Arc<AtomicBool>,
+    /// The number of bytes at the start of the file that are left out of the contents
+    base: u64,
+}
+
+/// Reads the input on a background thread.
+/// While following, reads never wait: they come back empty if nothing new has arrived yet.
+pub struct Reader {
+    receiver: Receiver<Message>,
+    /// The chunk being read
+    chunk: Vec<u8>,
+    /// How much of the chunk has been read
+    pos: usize,
+    /// Set when the end of the input was reached and nothing has arrived since
+    at_end: bool,
+    follow: Follow,
+    converted: Converted,
+    /// Sends the offsets to move to, if the input is a file read as it is
+    seeks: Option<Sender<u64>>,
+    /// The file the contents can be read back from, if any
+    source: Option<Source>,
+}
+
+/// What the background thread reads from
+enum Input {
+    /// A stream that is over once its end is reached (e.g. a pipe)
+    Stream(Box<dyn Read + Send>),
+    /// A file that may keep growing, or be rotated, after its end is reached
+    File(Tail),
+}
+
+/// How a followed file was rotated
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Rotation {
+    /// The path now points to another file, while the old one is still there to read back from
+    Replaced,
+    /// The file got shorter than what was read, losing its old contents (e.g. logrotate's copytruncate)
+    Truncated,
+}
+
+/// A file that is reopened when it is replaced or truncated (e.g. by logrotate)
+struct Tail {
+    path: PathBuf,
+    file: std::fs::File,
+    /// The identity of the opened file, to tell if the path now points to another one
+    id: Option<(u64, u64)>,
+    /// How much of the file has been read
+    pos: u64,
+}
+
+impl Tail {
+    fn open(path: &Path) -> std::io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        let id = file_id(&file.metadata()?);
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            id,
+            pos: 0,
+        })
+    }
+
+    /// Reopen the file if the path now points to another file, or if it got shorter than what was read.
+    /// Returns how it was rotated, if it was reopened.
+    fn reopen_if_rotated(&mut self) -> Option<Rotation> {
+        // The path may be missing for a moment while the file is being replaced
+        let metadata = std::fs::metadata(&self.path).ok()?;
+        let rotation = match file_id(&metadata) == self.id {
+            true if metadata.len() >= self.pos => return None,
+            true => Rotation::Truncated,
+            false => Rotation::Replaced,
+        };
+        *self = Tail::open(&self.path).ok()?;
+        Some(rotation)
+    }
+}
+
+/// The device and inode numbers identifying the file
+#[cfg(unix)]
+fn file_id(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+/// Files can't be told apart on this platform, so only truncation is detected
+#[cfg(not(unix))]
+fn file_id(_metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+impl Input {
+    /// Reopen the input if it is a file that has been rotated. Returns how it was rotated, if it was reopened.
+    fn reopen_if_rotated(&mut self) -> Option<Rotation> {
+        match self {
+            Input::Stream(_) => None,
+            Input::File(tail) => tail.reopen_if_rotated(),
+        }
+    }
+
+    /// Move to the given position of the file
+    fn seek(&mut self, pos: u64) -> std::io::Result<()> {
+        match self {
+            Input::Stream(_) => Err(std::io::ErrorKind::Unsupported.into()),
+            Input::File(tail) => {
+                tail.file.seek(SeekFrom::Start(pos))?;
+                tail.pos = pos;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Read for Input {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Input::Stream(stream) => stream.read(buf),
+            Input::File(tail) => {
+                let n = tail.file.read(buf)?;
+                tail.pos += n as u64;
+                Ok(n)
+            }
+        }
+    }
+}
+
+impl Reader {
+    /// Start reading a stream (e.g. STDIN) on a background thread, converting it from the encoding to UTF-8
+    pub fn stream<R>(input: R, encoding: Encoding) -> Self
+    where
+        R: Read + Send + 'static,
+    {
+        Self::spawn(Input::Stream(Box::new(input)), encoding, None)
+    }
+
+    /// Start reading the file on a background thread, converting it from the encoding to UTF-8.
+    /// Keeps checking for new contents after reaching its end, and follows it through rotations.
+    /// If the file is read as it is, the reader can be moved around it and its contents read back.
+    pub fn file(path: &Path, encoding: Encoding) -> std::io::Result<Self> {
+        let tail = Tail::open(path)?;
+        let source = Source::open(path, encoding)?;
+        Ok(Self::spawn(Input::File(tail), encoding, source))
+    }
+
+    fn spawn(input: Input, encoding: Encoding, source: Option<Source>) -> Self {
+        let (sender, receiver) = mpsc::sync_channel(READ_AHEAD);
+        let (seeks, requests) = match &source {
+            Some(source) => {
+                let (seeks, requests) = mpsc::channel();
+                let requests = Seeks {
+                    requests,
+                    limit: source.limit.clone(),
+                    truncated: source.truncated.clone(),
+                    base: source.base,
+                };
+                (Some(seeks), Some(requests))
+            }
+            None => (None, None),
+        };
+        let follow = Follow::default();
+        let converted = Converted::default();
+        let (switch, flag) = (follow.clone(), converted.clone());
+        std::thread::spawn(move || read_input(input, encoding, sender, requests, switch, flag));
+        Self {
+            receiver,
+            chunk: Vec::new(),
+            pos: 0,
+            at_end: false,
+            follow,
+            converted,
+            seeks,
+            source,
+        }
+    }
+
+    /// The switch that makes reads stop waiting for new contents
+    pub fn follow(&self) -> Follow {
+        self.follow.clone()
+    }
+
+    /// The flag telling whether the input is converted from another encoding
+    pub fn converted(&self) -> Converted {
+        self.converted.clone()
+    }
+
+    /// The file the contents can be read back from, if they are read from a file as they are
+    pub fn source(&self) -> Option<Source> {
+        self.source.clone()
+    }
+}
+
+/// Read the input in chunks and send them to the [Reader] until it goes away
+fn read_input(
+    mut input: Input,
+    encoding: Encoding,
+    sender: SyncSender<Message>,
+    seeks: Option<Seeks>,
+    follow: Follow,
+    converted: Converted,
+) {
+    let poll_at_end = matches!(input, Input::File(_));
+    let mut decoder = Decoder::new(encoding);
+    let mut buf = vec![0; CHUNK_SIZE];
+    let mut at_end = false;
+    let mut at_line_start = true;
+    // The length of the contents sent so far
+    let mut sent = 0;
+    loop {
+        // Taken before looking for what woke it, so that nothing that wakes it afterwards is missed
+        let wakes = follow.wakes();
+
+        // Move to where the reader asked to, if it did
+        if let Some(seeks) = &seeks {
+            if let Ok(offset) = seeks.requests.try_recv() {
+                let message = match input.seek(seeks.base + offset) {
+                    Ok(()) => Message::Seeked,
+                    Err(e) => Message::Error(e),
+                };
+                // The contents are read as they are, and there is no byte order mark past the start
+                decoder = Decoder::new(Encoding::Utf8);
+                at_end = false;
+                at_line_start = true;
+                sent = offset;
+                if sender.send(message).is_err() {
+                    return;
+                }
+                continue;
+            }
+        }
+
+        let message = match input.read(&mut buf) {
+            Ok(0) if at_end => {
+                // There's no looking for new contents until following is turned on (or the reader moves)
+                if !follow.is_on() {
+                    follow.wait(wakes);
+                    continue;
+                }
+                let Some(rotation) = input.reopen_if_rotated() else {
+                    std::thread::sleep(POLL_INTERVAL);
+                    continue;
+                };
+                // The contents from here on don't come from the file the reader can read them back from,
+                // and those before can't be read back either if the file was truncated
+                if let Some(seeks) = &seeks {
+                    seeks.limit.fetch_min(sent, Ordering::Relaxed);
+                    if rotation == Rotation::Truncated {
+                        seeks.truncated.store(true, Ordering::Relaxed);
+                    }
+                }
+                // Mark where the contents of the new file start, on a line of its own
+                let mut marker = if at_line_start { vec![] } else { vec![b'\n'] };
+                marker.extend_from_slice(ROTATED_MARKER);
+                // The new file may start with a byte order mark of its own
+                decoder = Decoder::new(encoding);
+                at_end = false;
+                at_line_start = true;
+                Message::Data(marker)
+            }
+            Ok(0) => {
+                at_end = true;
+                // A stream won't be getting the rest of a character cut short at its end
+                let rest = if poll_at_end {
+                    vec![]
+                } else {
+                    decoder.finish()
+                };
+                if !rest.is_empty() && sender.send(Message::Data(rest)).is_err() {
+                    return;
+                }
+                Message::End
+            }
+            Ok(n) => {
+                at_end = false;
+                let data = decoder.decode(&buf[..n]);
+                if decoder.converts() {
+                    converted.0.store(true, Ordering::Relaxed);
+                }
+                // Nothing to send until the rest of a character comes in, as an empty chunk would look like the end
+                let Some(&last) = data.last() else {
+                    continue;
+                };
+                at_line_start = last == b'\n';
+                Message::Data(data)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => Message::Error(e),
+        };
+        if let Message::Data(data) = &message {
+            sent += data.len() as u64;
+        }
+        let stop = matches!(message, Message::Error(_)) || (at_end && !poll_at_end);
+        if sender.send(message).is_err() || stop {
+            return;
+        }
+    }
+}
+
+impl Read for Reader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for Reader {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        if self.pos == self.chunk.len() {
+            // Only wait for the next chunk if there is one coming
+            let message = if self.at_end || self.follow.is_on() {
+                match self.receiver.try_recv() {
+                    Ok(message) => message,
+                    Err(TryRecvError::Empty | TryRecvError::Disconnected) => return Ok(&[]),
+                }
+            } else {
+                match self.receiver.recv() {
+                    Ok(message) => message,
+                    Err(_) => return Ok(&[]),
+                }
+            };
+            match message {
+                Message::Data(data) => {
+                    self.chunk = data;
+                    self.pos = 0;
+                    self.at_end = false;
+                }
+                Message::End => {
+                    self.at_end = true;
+                    return Ok(&[]);
+                }
+                Message::Error(e) => return Err(e),
+                // Only comes in answer to a seek, which waits for it
+                Message::Seeked => {}
+            }
+        }
+        Ok(&self.chunk[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.chunk.len());
+    }
+}
+
+/// Only files that are read as they are can be moved around, and only to an offset from the start
+impl Seek for Reader {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let (SeekFrom::Start(offset), Some(seeks)) = (pos, &self.seeks) else {
+            return Err(std::io::ErrorKind::Unsupported.into());
+        };
+        let gone = || std::io::Error::from(std::io::ErrorKind::BrokenPipe);
+        seeks.send(offset).map_err(|_| gone())?;
+        self.follow.wake();
+        // Drop the chunks that were read ahead from before the offset
+        loop {
+            match self.receiver.recv().map_err(|_| gone())? {
+                Message::Seeked => break,
+                Message::Error(e) => return Err(e),
+                Message::Data(_) | Message::End => {}
+            }
+        }
+        self.chunk.clear();
+        self.pos = 0;
+        self.at_end = false;
+        Ok(offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn should_read_the_whole_input() {
+        let input = std::io::Cursor::new(b"one\ntwo\nthree".to_vec());
+        let lines: Vec<String> = Reader::stream(input, Encoding::Auto)
+            .lines()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(vec!["one", "two", "three"], lines);
+    }
+
+    #[test]
+    fn should_not_wait_for_new_contents_while_following() {
+        let (pipe, mut writer) = std::io::pipe().unwrap();
+        let mut reader = Reader::stream(pipe, Encoding::Auto);
+        reader.follow().set(true);
+        assert!(reader.fill_buf().unwrap().is_empty());
+
+        writer.write_all(b"more\n").unwrap();
+        let mut line = String::new();
+        while line.is_empty() {
+            reader.read_line(&mut line).unwrap();
+        }
+        assert_eq!("more\n", line);
+    }
+
+    /// Read from the followed reader until `len` bytes arrive or it takes too long
+    fn read_followed(reader: &mut Reader, len: usize) -> String {
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        let mut text = String::new();
+        while text.len() < len && std::time::Instant::now() < deadline {
+            if reader.read_line(&mut text).unwrap() == 0 {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        }
+        text
+    }
+
+    #[test]
+    fn should_move_around_a_file_read_as_it_is() {
+        let path = std::env::temp_dir().join(format!("scan-test-seek-{}", std::process::id()));
+        std::fs::write(&path, "\u{FEFF}one\ntwo\nthree\n").unwrap();
+        let mut reader = Reader::file(&path, Encoding::Auto).unwrap();
+        let source = reader.source().unwrap();
+        assert_eq!(14, source.len().unwrap());
+
+        reader.seek(SeekFrom::Start(8)).unwrap();
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!("three\n", line);
+
+        let mut buf = [0; 3];
+        assert_eq!(3, source.read_at(4, &mut buf).unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(b"two", &buf);
+    }
+
+    #[test]
+    fn should_wake_up_at_the_end_of_a_file_to_follow_or_move_around_it() {
+        let path = std::env::temp_dir().join(format!("scan-test-wake-{}", std::process::id()));
+        std::fs::write(&path, "one\ntwo\n").unwrap();
+        let mut reader = Reader::file(&path, Encoding::Auto).unwrap();
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        // Give the background thread the time to start waiting at the end
+        std::thread::sleep(Duration::from_millis(200));
+
+        reader.seek(SeekFrom::Start(4)).unwrap();
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!("two\n", line);
+
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"three\n")
+            .unwrap();
+        reader.follow().set(true);
+        let text = read_followed(&mut reader, 6);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!("three\n", text);
+    }
+
+    #[test]
+    fn should_not_move_around_a_converted_input() {
+        let input = std::io::Cursor::new(b"one\ntwo\n".to_vec());
+        let mut reader = Reader::stream(input, Encoding::Auto);
+        assert!(reader.source().is_none());
+        assert!(reader.seek(SeekFrom::Start(4)).is_err());
+    }
+
+    #[test]
+    fn should_tell_when_the_input_is_converted() {
+        let input = std::io::Cursor::new(b"\xFF\xFEh\0i\0\n\0".to_vec());
+        let mut reader = Reader::stream(input, Encoding::Auto);
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!("hi\n", line);
+        assert!(reader.converted().is_set());
+
+        let input = std::io::Cursor::new(b"hi\n".to_vec());
+        let mut reader = Reader::stream(input, Encoding::Auto);
+        reader.read_line(&mut line).unwrap();
+        assert!(!reader.converted().is_set());
+    }
+
+    #[test]
+    fn should_reopen_the_file_when_it_is_truncated() {
+        let path = std::env::temp_dir().join(format!("scan-test-truncate-{}", std::process::id()));
+        std::fs::write(&path, "one\ntwo\n").unwrap();
+        let mut reader = Reader::file(&path, Encoding::Auto).unwrap();
+        reader.follow().set(true);
+        assert_eq!("one\ntwo\n", read_followed(&mut reader, 8));
+
+        std::fs::write(&path, "three\n").unwrap();
+        let expected = "--- file rotated ---\nthree\n";
+        let text = read_followed(&mut reader, expected.len());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(expected, text);
+    }
+
+    #[test]
+    fn should_reopen_the_file_when_it_is_replaced() {
+        let path = std::env::temp_dir().join(format!("scan-test-replace-{}", std::process::id()));
+        let rotated = path.with_extension("1");
+        std::fs::write(&path, "one\npartial").unwrap();
+        let mut reader = Reader::file(&path, Encoding::Auto).unwrap();
+        reader.follow().set(true);
+        assert_eq!("one\npartial", read_followed(&mut reader, 11));
+
+        std::fs::rename(&path, &rotated).unwrap();
+        std::fs::write(&path, "two\n").unwrap();
+        let expected = "\n--- file rotated ---\ntwo\n";
+        let text = read_followed(&mut reader, expected.len());
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&rotated).unwrap();
+        assert_eq!(expected, text);
+    }
+}
@@ -0,0 +1,267 @@
+use std::ops::Range;
+
+use crossterm::style::Color;
+use regex::{Regex, RegexBuilder};
+
+/// The colours given to pinned highlights, in order
+const HIGHLIGHT_COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::Green,
+    Color::Magenta,
+    Color::Red,
+    Color::Blue,
+    Color::DarkYellow,
+];
+
+/// A compiled regular-expression search pattern
+#[derive(Clone)]
+pub struct Pattern {
+    regex: Regex,
+    /// Whether the pattern ignores case
+    ignore_case: bool,
+}
+
+/// Describes how a search pattern treats upper and lower case letters
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Case {
+    /// Case-insensitive unless the query contains an uppercase letter
+    #[default]
+    Smart,
+    /// Always case-insensitive
+    Insensitive,
+    /// Always case-sensitive
+    Sensitive,
+}
+
+impl Case {
+    /// Cycle through to the next case mode
+    pub fn next(self) -> Self {
+        match self {
+            Case::Smart => Case::Insensitive,
+            Case::Insensitive => Case::Sensitive,
+            Case::Sensitive => Case::Smart,
+        }
+    }
+
+    /// Determine whether the given query should be matched case-insensitively
+    pub fn ignores_case(self, query: &str) -> bool {
+        match self {
+            Case::Smart => !has_uppercase(query),
+            Case::Insensitive => true,
+            Case::Sensitive => false,
+        }
+    }
+}
+
+/// Returns true if the query contains an uppercase letter.
+/// Letters that are part of an escape sequence (like `\D` or `\W`) are not counted.
+fn has_uppercase(query: &str) -> bool {
+    let mut chars = query.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            chars.next(); // Skip the escaped character
+        } else if c.is_uppercase() {
+            return true;
+        }
+    }
+    false
+}
+
+/// The location of a match in the buffer
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct Match {
+    /// The index of the line containing the match
+    pub line: usize,
+    /// The byte offset where the match begins
+    pub start: usize,
+    /// The byte offset where the match ends
+    pub end: usize,
+}
+
+impl Pattern {
+    /// Compile the given query into a search pattern
+    pub fn new(query: &str, case: Case) -> Result<Self, regex::Error> {
+        let ignore_case = case.ignores_case(query);
+        let regex = RegexBuilder::new(query)
+            .case_insensitive(ignore_case)
+            .build()?;
+        Ok(Self { regex, ignore_case })
+    }
+
+    /// The query the pattern was compiled from
+    pub fn as_str(&self) -> &str {
+        self.regex.as_str()
+    }
+
+    /// Iterate over the byte ranges of all (non-empty) matches in the line.
+    /// Only the visible text of the line is searched, leaving out its escape codes (e.g. those that `-R` passes
+    /// through, or that overstrikes are turned into), but the ranges are those of the bytes in the line.
+    pub fn find_iter(&self, line: &str) -> std::vec::IntoIter<Range<usize>> {
+        let matches: Vec<Range<usize>> = match super::strip_escapes(line) {
+            Some((text, offsets)) => self
+                .matches(&text)
+                .map(|r| offsets[r.start]..offsets[r.end - 1] + 1)
+                .collect(),
+            None => self.matches(line).collect(),
+        };
+        matches.into_iter()
+    }
+
+    /// The byte ranges of all (non-empty) matches in the text
+    fn matches<'a>(&'a self, text: &'a str) -> impl Iterator<Item = Range<usize>> + 'a {
+        self.regex
+            .find_iter(text)
+            .filter(|m| !m.is_empty())
+            .map(|m| m.range())
+    }
+
+    /// Returns true if the line contains a match
+    pub fn is_match(&self, line: &str) -> bool {
+        self.find_iter(line).next().is_some()
+    }
+
+    /// Find the first match in the line that starts at or after the byte offset `from`
+    pub fn next(&self, line: &str, from: usize) -> Option<Range<usize>> {
+        self.find_iter(line).find(|r| r.start >= from)
+    }
+
+    /// Find the last match in the line that starts before the byte offset `before`
+    pub fn prev(&self, line: &str, before: usize) -> Option<Range<usize>> {
+        self.find_iter(line).take_while(|r| r.start < before).last()
+    }
+}
+
+/// A pattern pinned to be highlighted in a colour of its own
+#[derive(Clone, PartialEq, Eq)]
+pub struct Highlight {
+    pub pattern: Pattern,
+    pub color: Color,
+}
+
+impl Highlight {
+    /// Pin the pattern with the first colour that isn't already used by the existing highlights
+    pub fn new(pattern: Pattern, existing: &[Highlight]) -> Self {
+        let color = HIGHLIGHT_COLORS
+            .into_iter()
+            .find(|c| existing.iter().all(|h| h.color != *c))
+            .unwrap_or(HIGHLIGHT_COLORS[existing.len() % HIGHLIGHT_COLORS.len()]);
+        Self { pattern, color }
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str() && self.ignore_case == other.ignore_case
+    }
+}
+
+impl Eq for Pattern {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_find_all_regex_matches() {
+        let pattern = Pattern::new(r"ERROR|WARN \d+", Case::Sensitive).unwrap();
+        let matches: Vec<_> = pattern.find_iter("WARN 42: ERROR occurred").collect();
+        assert_eq!(vec![0..7, 9..14], matches);
+    }
+
+    #[test]
+    fn should_skip_empty_matches() {
+        let pattern = Pattern::new("x*", Case::Smart).unwrap();
+        assert_eq!(0, pattern.find_iter("abc").count());
+        assert_eq!(Some(1..3), pattern.next("axx", 0));
+    }
+
+    #[test]
+    fn should_find_next_and_previous_matches() {
+        let pattern = Pattern::new("ab", Case::Smart).unwrap();
+        let line = "ab ab ab";
+        assert_eq!(Some(3..5), pattern.next(line, 1));
+        assert_eq!(None, pattern.next(line, 7));
+        assert_eq!(Some(3..5), pattern.prev(line, 6));
+        assert_eq!(None, pattern.prev(line, 0));
+    }
+
+    #[test]
+    fn should_ignore_case_when_query_is_lowercase_in_smart_mode() {
+        let pattern = Pattern::new("error", Case::Smart).unwrap();
+        assert_eq!(3, pattern.find_iter("Error ERROR error").count());
+    }
+
+    #[test]
+    fn should_respect_case_when_query_has_uppercase_in_smart_mode() {
+        let pattern = Pattern::new("Error", Case::Smart).unwrap();
+        assert_eq!(
+            vec![0..5],
+            pattern.find_iter("Error ERROR error").collect::<Vec<_>>()
+        );
+        // Escape sequences like `\W` do not count as uppercase letters
+        let pattern = Pattern::new(r"error\W", Case::Smart).unwrap();
+        assert_eq!(2, pattern.find_iter("Error! ERROR!").count());
+    }
+
+    #[test]
+    fn should_respect_explicit_case_modes() {
+        let insensitive = Pattern::new("Error", Case::Insensitive).unwrap();
+        assert_eq!(3, insensitive.find_iter("Error ERROR error").count());
+        let sensitive = Pattern::new("error", Case::Sensitive).unwrap();
+        assert_eq!(1, sensitive.find_iter("Error ERROR error").count());
+    }
+
+    #[test]
+    fn should_give_highlights_distinct_colours() {
+        let mut highlights = Vec::new();
+        for query in ["a", "b", "c"] {
+            let pattern = Pattern::new(query, Case::Smart).unwrap();
+            highlights.push(Highlight::new(pattern, &highlights));
+        }
+        assert_eq!(Color::Cyan, highlights[0].color);
+        assert_eq!(Color::Green, highlights[1].color);
+        assert_eq!(Color::Magenta, highlights[2].color);
+        // Colours freed up by removed highlights are reused
+        highlights.remove(1);
+        let pattern = Pattern::new("d", Case::Smart).unwrap();
+        assert_eq!(Color::Green, Highlight::new(pattern, &highlights).color);
+    }
+
+    #[test]
+    fn should_not_match_inside_the_escape_codes_of_an_overstruck_line() {
+        // How `man` makes a heading bold, once its overstrikes are turned into escape codes
+        let line = crate::helpers::sanitize(
+            "N\x08NA\x08AM\x08ME\x08E",
+            crate::helpers::ControlChars::Caret,
+        );
+        assert_eq!("\x1b[1mNAME\x1b[22m", line);
+        let pattern = Pattern::new("m", Case::Smart).unwrap();
+        assert_eq!(vec![6..7], pattern.find_iter(&line).collect::<Vec<_>>());
+        assert_eq!(
+            Some(4..8),
+            Pattern::new("name", Case::Smart).unwrap().next(&line, 0)
+        );
+        assert!(!Pattern::new("22", Case::Smart).unwrap().is_match(&line));
+    }
+
+    #[test]
+    fn should_not_match_inside_the_escape_codes_of_a_coloured_line() {
+        // As passed through by `-R`
+        let line = "\x1b[31mred\x1b[0m";
+        for query in ["3", "m", "0"] {
+            let pattern = Pattern::new(query, Case::Smart).unwrap();
+            assert_eq!(None, pattern.find_iter(line).next(), "{query}");
+        }
+        let pattern = Pattern::new("e", Case::Smart).unwrap();
+        assert_eq!(vec![6..7], pattern.find_iter(line).collect::<Vec<_>>());
+        assert_eq!(
+            Some(5..8),
+            Pattern::new("red", Case::Smart).unwrap().prev(line, 12)
+        );
+    }
+
+    #[test]
+    fn should_report_invalid_patterns() {
+        assert!(Pattern::new("(unclosed", Case::Smart).is_err());
+    }
+}
@@ -0,0 +1,170 @@
+/// The number of lines between the remembered states of the [SgrIndex]
+pub const CHECKPOINT_INTERVAL: usize = 256;
+
+/// The graphics attributes (colours, bold, underline, ...) set by SGR escape codes (`ESC[...m`)
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct SgrState {
+    /// The attributes that are on (bit n is set for the attribute code n: 1 is bold, 4 is underline, ...)
+    attributes: u16,
+    /// The codes of the foreground colour, if set (e.g. `[31]` or `[38, 5, 208]`)
+    fg: Option<Vec<u16>>,
+    /// The codes of the background colour, if set
+    bg: Option<Vec<u16>>,
+}
+
+impl SgrState {
+    /// Apply the SGR escape codes found in the text
+    pub fn update(&mut self, text: &str) {
+        let mut rest = text;
+        while let Some(start) = rest.find("\x1b[") {
+            rest = &rest[start + 2..];
+            let Some(end) = rest.find(|c| matches!(c, '\x40'..='\x7E')) else {
+                return;
+            };
+            if rest[end..].starts_with('m') {
+                self.apply(&rest[..end]);
+            }
+            rest = &rest[end + 1..];
+        }
+    }
+
+    /// Apply the parameters of a single SGR escape code (e.g. `1;31`)
+    fn apply(&mut self, params: &str) {
+        let mut codes = params
+            .split([';', ':'])
+            .map(|p| p.parse::<u16>().unwrap_or(0));
+        while let Some(code) = codes.next() {
+            match code {
+                0 => *self = SgrState::default(),
+                1..=9 => self.attributes |= 1 << code,
+                21 | 22 => self.attributes &= !(1 << 1 | 1 << 2),
+                23 => self.attributes &= !(1 << 3),
+                24 => self.attributes &= !(1 << 4),
+                25 => self.attributes &= !(1 << 5 | 1 << 6),
+                27..=29 => self.attributes &= !(1 << (code - 20)),
+                30..=37 | 90..=97 => self.fg = Some(vec![code]),
+                40..=47 | 100..=107 => self.bg = Some(vec![code]),
+                38 => self.fg = extended_color(code, &mut codes),
+                48 => self.bg = extended_color(code, &mut codes),
+                39 => self.fg = None,
+                49 => self.bg = None,
+                _ => {}
+            }
+        }
+    }
+
+    /// The escape code that sets this state from a clean slate. Empty if nothing is set.
+    pub fn to_escape(&self) -> String {
+        let mut params: Vec<String> = (1..=9)
+            .filter(|n| self.attributes & (1 << n) != 0)
+            .map(|n| n.to_string())
+            .collect();
+        for color in [&self.fg, &self.bg].into_iter().flatten() {
+            params.extend(color.iter().map(u16::to_string));
+        }
+        if params.is_empty() {
+            return String::new();
+        }
+        format!("\x1b[{}m", params.join(";"))
+    }
+}
+
+/// Read the rest of an extended colour code: `38;5;n` (256 colours) or `38;2;r;g;b` (true colour)
+fn extended_color(code: u16, codes: &mut impl Iterator<Item = u16>) -> Option<Vec<u16>> {
+    let mut color = vec![code];
+    match codes.next()? {
+        5 => color.extend([5, codes.next()?]),
+        2 => color.extend([2, codes.next()?, codes.next()?, codes.next()?]),
+        _ => return None,
+    }
+    Some(color)
+}
+
+/// Remembers the SGR state at regular intervals of the lines,
+/// so that the state at the start of any line can be worked out without going through all the lines before it
+#[derive(Default)]
+pub struct SgrIndex {
+    /// The state at the start of every `CHECKPOINT_INTERVAL`-th line
+    checkpoints: Vec<SgrState>,
+    /// The state at the end of the last line
+    tail: SgrState,
+    /// The number of lines accounted for
+    len: usize,
+}
+
+impl SgrIndex {
+    /// Account for the next line
+    pub fn push(&mut self, line: &str) {
+        if self.len.is_multiple_of(CHECKPOINT_INTERVAL) {
+            self.checkpoints.push(self.tail.clone());
+        }
+        self.tail.update(line);
+        self.len += 1;
+    }
+
+    /// The state at the start of the line at the given index.
+    /// Takes the lines before it, back to the last checkpoint (the lines since the last multiple of `CHECKPOINT_INTERVAL`).
+    pub fn state_at(&self, index: usize, lines: &[String]) -> SgrState {
+        let index = index.min(self.len);
+        let checkpoint = index / CHECKPOINT_INTERVAL;
+        let mut state = self
+            .checkpoints
+            .get(checkpoint)
+            .cloned()
+            .unwrap_or_default();
+        for line in lines.iter().take(index % CHECKPOINT_INTERVAL) {
+            state.update(line);
+        }
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(text: &str) -> SgrState {
+        let mut state = SgrState::default();
+        state.update(text);
+        state
+    }
+
+    #[test]
+    fn should_combine_the_codes_into_a_single_escape() {
+        assert_eq!("\x1b[1;31m", state("\x1b[31mred \x1b[1mbold").to_escape());
+        assert_eq!(
+            "\x1b[38;5;208;48;2;1;2;3m",
+            state("\x1b[38;5;208;48;2;1;2;3m").to_escape()
+        );
+    }
+
+    #[test]
+    fn should_turn_codes_off() {
+        assert_eq!("", state("\x1b[1;31mx\x1b[0m").to_escape());
+        assert_eq!("\x1b[31m", state("\x1b[1;4;31mx\x1b[22;24m").to_escape());
+        assert_eq!("", state("\x1b[31mx\x1b[m").to_escape());
+    }
+
+    #[test]
+    fn should_ignore_other_escape_codes() {
+        assert_eq!("\x1b[32m", state("\x1b[32m\x1b[2K\x1b[1A").to_escape());
+    }
+
+    #[test]
+    fn should_work_out_the_state_at_the_start_of_any_line() {
+        let mut lines: Vec<String> = (0..600).map(|i| format!("line {i}")).collect();
+        lines[10] = "\x1b[33mopened".into();
+        lines[400] = "closed\x1b[0m".into();
+        let mut index = SgrIndex::default();
+        for line in &lines {
+            index.push(line);
+        }
+        assert_eq!("", index.state_at(10, &lines[..10]).to_escape());
+        assert_eq!("\x1b[33m", index.state_at(11, &lines[..11]).to_escape());
+        assert_eq!(
+            "\x1b[33m",
+            index.state_at(300, &lines[256..300]).to_escape()
+        );
+        assert_eq!("", index.state_at(401, &lines[256..401]).to_escape());
+    }
+}
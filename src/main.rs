@@ -1,5 +1,5 @@
 use clap::Parser;
-use crossterm::{cursor, terminal, tty::IsTty, ExecutableCommand};
+use crossterm::{cursor, event, terminal, tty::IsTty, ExecutableCommand};
 
 mod cli;
 mod helpers;
@@ -24,17 +24,39 @@ fn run(args: &cli::Args) -> Result<(), Box<dyn std::error::Error>> {
     // Get a reference to the standard output
     let mut stdout = std::io::stdout();
 
-    // Instantiate a reader to read from. This can be a file or standard input
-    let mut reader = helpers::get_reader(&args.file)?;
-
     // Determine if we are in passthrough mode.
     // If the `passthrough` flag is set, or the terminal is not interactive...
-    // we simply pipe the output through
+    // we simply pipe the contents through as they are, one file after the other
     if args.passthrough || !stdout.is_tty() {
-        std::io::copy(&mut reader, &mut stdout)?;
+        match args.files.is_empty() {
+            true => {
+                std::io::copy(&mut helpers::get_source(None)?, &mut stdout)?;
+            }
+            false => {
+                for file in &args.files {
+                    std::io::copy(&mut helpers::get_source(Some(file))?, &mut stdout)?;
+                }
+            }
+        }
         return Ok(());
     }
 
+    // Run the files through the user's preprocessor, if they have one
+    let preprocessor = match args.no_preprocessor {
+        true => None,
+        false => helpers::Preprocessor::from_env(),
+    };
+
+    // Instantiate a reader to read from for each of the files, or for standard input if there are none
+    let readers = match args.files.is_empty() {
+        true => vec![helpers::get_reader(None, args.encoding, None)?],
+        false => args
+            .files
+            .iter()
+            .map(|file| helpers::get_reader(Some(file), args.encoding, preprocessor.as_ref()))
+            .collect::<Result<_, _>>()?,
+    };
+
     // Initialize the Pager application
     let size = crossterm::terminal::size()?;
     let mut pager = pager::Pager::init(size);
@@ -43,17 +65,47 @@ fn run(args: &cli::Args) -> Result<(), Box<dyn std::error::Error>> {
     pager
         .with_line_numbers(args.show_line_numbers)
         .with_borders(args.show_borders)
-        .all(args.all);
-
-    if let Some(file) = &args.file {
-        pager.with_offset(file.row, file.col);
+        .with_wrap(args.wrap)
+        .with_tabs(args.tabs)
+        .with_control(if args.raw {
+            helpers::ControlChars::Raw
+        } else if args.ansi {
+            helpers::ControlChars::Sgr
+        } else {
+            helpers::ControlChars::Caret
+        })
+        .with_case(if args.ignore_case {
+            helpers::Case::Insensitive
+        } else {
+            helpers::Case::Smart
+        })
+        .with_history(helpers::history_path());
+
+    // Add the files, each starting at the line and column given with it
+    match args.files.is_empty() {
+        true => {
+            pager.with_file("STDIN", readers[0].follow(), None, readers[0].converted());
+        }
+        false => {
+            for (file, reader) in args.files.iter().zip(&readers) {
+                pager
+                    .with_file(
+                        &file.filename,
+                        reader.follow(),
+                        reader.source(),
+                        reader.converted(),
+                    )
+                    .with_offset(file.row, file.col);
+            }
+        }
     }
+    pager.with_follow(args.follow).all(args.all);
 
     // Setup the terminal before running the application
     setup(&mut stdout)?;
 
     // Run the Pager application
-    pager.run(reader, &mut stdout)?;
+    pager.run(readers, &mut stdout)?;
 
     // Cleanup the terminal after the Pager application exits
     cleanup(&mut stdout)?;
@@ -64,12 +116,15 @@ fn run(args: &cli::Args) -> Result<(), Box<dyn std::error::Error>> {
 /// Prepares the terminal for the application.
 /// Switches to the Alternate Screen Buffer and clears the screen.
 /// Also moves the cursor to the top and hides it.
+/// Enables raw mode and bracketed paste so that every key press and paste reaches the command-line.
 /// Registers a panic-hook to automatically call the `cleanup` function
 fn setup(stdout: &mut std::io::Stdout) -> Result<(), Box<dyn std::error::Error>> {
     stdout.execute(terminal::EnterAlternateScreen)?;
     stdout.execute(terminal::Clear(terminal::ClearType::All))?;
     stdout.execute(cursor::MoveTo(0, 0))?;
     stdout.execute(cursor::Hide)?;
+    stdout.execute(event::EnableBracketedPaste)?;
+    terminal::enable_raw_mode()?;
 
     // Create a custom hook to handle graceful cleanup of the terminal when panicking
     let original_panic = std::panic::take_hook();
@@ -85,6 +140,8 @@ fn setup(stdout: &mut std::io::Stdout) -> Result<(), Box<dyn std::error::Error>>
 
 /// Restore the terminal by exiting the Alternate Screen Buffer when we're done. Also re-enables the cursor.
 fn cleanup(stdout: &mut std::io::Stdout) -> Result<(), Box<dyn std::error::Error>> {
+    terminal::disable_raw_mode()?;
+    stdout.execute(event::DisableBracketedPaste)?;
     stdout.execute(terminal::LeaveAlternateScreen)?;
     stdout.execute(cursor::Show)?;
     Ok(())
@@ -0,0 +1,204 @@
+use std::io::Write;
+use std::time::Instant;
+
+use crossterm::{terminal, QueueableCommand};
+
+use super::{search::SCAN_TIME_SLICE, ui::Mode, Pager, PreviousFrame};
+use crate::helpers::{self, Command, Setting};
+
+/// The most bytes read back from the backing at a time when writing them to a file
+const WRITE_BLOCK_SIZE: usize = 1024 * 1024;
+
+/// The writing of the lines shown in the view to a file, in progress.
+/// The lines are written (and the rest of the input read) a slice of time at a time.
+pub(super) struct Writing {
+    file: std::io::BufWriter<std::fs::File>,
+    path: String,
+    /// The next row to write
+    row: usize,
+    /// The offsets of the bytes of the consecutive lines to write together
+    bytes: std::ops::Range<usize>,
+}
+
+impl Pager {
+    /// Run the command in the command-line input, then return to base mode
+    pub(super) fn command<T>(
+        &mut self,
+        reader: T,
+        stdout: &mut std::io::Stdout,
+    ) -> std::io::Result<()>
+    where
+        T: std::io::BufRead,
+    {
+        let input = std::mem::take(&mut self.command_line.input);
+        self.command_line.mode = Mode::Base;
+        let result = match helpers::parse_command(&input) {
+            Ok(Command::Set(settings)) => settings
+                .iter()
+                .try_for_each(|setting| self.set(setting, stdout)),
+            Ok(Command::Write(path)) => self.write(reader, &path),
+            Ok(Command::Filter(pattern)) => self.filter(&pattern).map_err(|e| e.to_string()),
+            Ok(Command::Next) => self.next_file(),
+            Ok(Command::Prev) => self.prev_file(),
+            Ok(Command::Quit) => {
+                self.exit();
+                Ok(())
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            self.command_line.message = e;
+        }
+        Ok(())
+    }
+
+    /// Change the option named by the setting
+    fn set(&mut self, setting: &Setting, stdout: &mut std::io::Stdout) -> Result<(), String> {
+        match setting.name() {
+            "number" | "nu" => {
+                self.view.show_line_numbers = setting.switch(self.view.show_line_numbers)?;
+            }
+            "wrap" => {
+                self.view.wrap = setting.switch(self.view.wrap)?;
+            }
+            "tabstop" | "ts" => {
+                let Setting::Value(_, value) = setting else {
+                    return Err(format!("Option {} needs a value", setting.name()));
+                };
+                self.view.tabstop = match value.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("Invalid tab stop: {value}")),
+                };
+            }
+            "hexdump" | "hex" => {
+                if setting.switch(self.hexdump.on)? != self.hexdump.on {
+                    self.toggle_hexdump().map_err(|e| e.to_string())?;
+                }
+            }
+            "borders" => {
+                self.view.show_borders = setting.switch(self.view.show_borders)?;
+                // The borders are drawn around the view during setup
+                self.redraw(stdout).map_err(|e| e.to_string())?;
+            }
+            name => return Err(format!("Unknown option: {name}")),
+        }
+        Ok(())
+    }
+
+    /// Start writing the lines shown in the view (all of them, not just the visible ones) to the file.
+    /// The raw bytes they were read from are written, rather than the lines as they're shown.
+    /// The rest of the input is read as they're written, and the writing carries on on each iteration of the event loop.
+    fn write<T>(&mut self, reader: T, path: &str) -> Result<(), String>
+    where
+        T: std::io::BufRead,
+    {
+        if self.writing.is_some() {
+            return Err(String::from("Already writing to a file"));
+        }
+        let file =
+            std::fs::File::create(path).map_err(|e| format!("Could not write {path}: {e}"))?;
+        self.writing = Some(Writing {
+            file: std::io::BufWriter::new(file),
+            path: path.to_string(),
+            row: 0,
+            bytes: 0..0,
+        });
+        self.continue_write(reader);
+        Ok(())
+    }
+
+    /// Carry on writing the lines to the file (if it's in progress) for a slice of time
+    pub(super) fn continue_write<T>(&mut self, mut reader: T)
+    where
+        T: std::io::BufRead,
+    {
+        // The lines being skipped through are written once they've all been gone through
+        if self.skip.is_some() {
+            return;
+        }
+        let Some(mut writing) = self.writing.take() else {
+            return;
+        };
+        let deadline = Instant::now() + SCAN_TIME_SLICE;
+        let result = loop {
+            // Out of time. Pick up where we left off on the next iteration of the event loop
+            if Instant::now() >= deadline {
+                self.command_line.writing = Some(writing.row);
+                self.writing = Some(writing);
+                return;
+            }
+            // Write the lines shown so far, then wait for the filter to catch up with the lines read,
+            // and read more of the input until its end
+            let step = if writing.row < self.content().len() {
+                self.write_row(&mut writing)
+            } else if self.filtering() {
+                self.update_filter()
+            } else {
+                match self.read_lines(&mut reader, self.view.height.max(1)) {
+                    Ok(0) => break self.finish_write(&mut writing),
+                    Ok(_) => Ok(()),
+                    Err(e) => Err(e),
+                }
+            };
+            if let Err(e) = step {
+                break Err(e);
+            }
+        };
+        self.command_line.writing = None;
+        self.command_line.message = match result {
+            Ok(()) => format!("Wrote {} lines to {}", writing.row, writing.path),
+            Err(e) => format!("Could not write {}: {e}", writing.path),
+        };
+    }
+
+    /// Add the bytes of the line at the writing's row to those to write, writing them out once they're no longer consecutive
+    fn write_row(&mut self, writing: &mut Writing) -> std::io::Result<()> {
+        self.load_row(writing.row)?;
+        let range = self
+            .content()
+            .index(writing.row)
+            .and_then(|i| self.lines.range(i));
+        writing.row += 1;
+        let Some(range) = range else {
+            return Ok(());
+        };
+        let bytes = &mut writing.bytes;
+        if range.start != bytes.end || bytes.len() >= WRITE_BLOCK_SIZE {
+            writing
+                .file
+                .write_all(&self.backing.read(bytes.start, bytes.len())?)?;
+            bytes.start = range.start;
+        }
+        bytes.end = range.end;
+        Ok(())
+    }
+
+    /// Write out the last of the bytes to the file
+    fn finish_write(&mut self, writing: &mut Writing) -> std::io::Result<()> {
+        let bytes = &writing.bytes;
+        writing
+            .file
+            .write_all(&self.backing.read(bytes.start, bytes.len())?)?;
+        writing.file.flush()
+    }
+
+    /// Stop writing the lines to the file, and remove the file as it's only partly written
+    pub(super) fn cancel_write(&mut self) {
+        if let Some(writing) = self.writing.take() {
+            drop(writing.file);
+            // The file may be gone already, which is just as well
+            let _ = std::fs::remove_file(&writing.path);
+            self.command_line.writing = None;
+            self.command_line.message = String::from("Write cancelled");
+        }
+    }
+
+    /// Clear the screen and draw everything again from scratch
+    fn redraw(&mut self, stdout: &mut std::io::Stdout) -> std::io::Result<()> {
+        stdout.queue(terminal::Clear(terminal::ClearType::All))?;
+        self.setup(stdout)?;
+        self.prev = PreviousFrame::default();
+        self.revision += 1;
+        Ok(())
+    }
+}
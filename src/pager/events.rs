@@ -1,27 +1,91 @@
-use crossterm::event::{Event, KeyCode, KeyEventKind};
+use std::time::Duration;
 
-use super::{ui::Mode, Pager};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+use super::{filter, follow, hexdump, lines::Then, search, ui::Mode, Pager};
 use crate::helpers;
 
 impl Pager {
     /// Handle crossterm events like key-presses, mouse-scroll and window resize
     pub fn handle_events<T>(
         &mut self,
-        reader: T,
+        mut reader: T,
         stdout: &mut std::io::Stdout,
     ) -> Result<(), Box<dyn std::error::Error>>
     where
-        T: std::io::BufRead,
+        T: std::io::BufRead + std::io::Seek,
     {
+        // While a scan, the count of the matches, the filtering, the skipping through the lines
+        // or the writing to a file is in progress, don't wait around for events so that it can carry on
+        let busy = self.scan.is_some()
+            || self.counting()
+            || self.filtering()
+            || self.skip.is_some()
+            || self.writing.is_some();
+        if busy && !crossterm::event::poll(Duration::ZERO)? {
+            return Ok(());
+        }
+
+        // While following, only wait a little so that we can check the input for new contents
+        if self.follow.is_on() && !crossterm::event::poll(follow::FOLLOW_POLL_INTERVAL)? {
+            return Ok(());
+        }
+
         // Read crossterm event
         let event = crossterm::event::read()?;
 
+        // Esc cancels the scan in progress
+        if self.scan.is_some() {
+            if let Event::Key(KeyEvent {
+                code: KeyCode::Esc,
+                kind: KeyEventKind::Press,
+                ..
+            }) = event
+            {
+                self.cancel_scan();
+                return Ok(());
+            }
+        }
+
+        // Esc cancels the filtering in progress
+        if self.filtering() {
+            if let Event::Key(KeyEvent {
+                code: KeyCode::Esc,
+                kind: KeyEventKind::Press,
+                ..
+            }) = event
+            {
+                self.cancel_filter()?;
+                return Ok(());
+            }
+        }
+
+        // Clear any stale status message once the user presses a key
+        if matches!(event, Event::Key(_)) {
+            self.command_line.message.clear();
+        }
+
+        // While skipping through the lines or writing them to a file, the view can only be scrolled around.
+        // Esc cancels the skipping or the writing
+        if self.skip.is_some() || self.writing.is_some() {
+            return self.handle_busy_events(event, reader, stdout);
+        }
+
         // Call sub-component event-handlers
         // If the event handlers returns a true, then the event propagation must stop now and we exit early
-        if self.command_line.handle_events(&event)? {
+        let prompt = search::Prompt {
+            mode: self.command_line.mode.clone(),
+            input: self.command_line.input.clone(),
+            case: self.command_line.case,
+        };
+        let stop = self.command_line.handle_events(&event)?;
+        self.incremental_search(prompt, &mut reader)?;
+        if stop {
             return Ok(());
         }
-        if self.view.handle_events(&event, &self.lines)? {
+        self.load_view()?;
+        let content = filter::content(&self.lines, self.filter.as_ref(), self.skip.as_ref());
+        if self.view.handle_events(&event, content)? {
             return Ok(());
         }
 
@@ -31,6 +95,39 @@ impl Pager {
         Ok(())
     }
 
+    /// Handle the events while skipping through the lines of a file, or writing them to one
+    fn handle_busy_events<T>(
+        &mut self,
+        event: Event,
+        mut reader: T,
+        stdout: &mut std::io::Stdout,
+    ) -> Result<(), Box<dyn std::error::Error>>
+    where
+        T: std::io::BufRead + std::io::Seek,
+    {
+        match event {
+            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
+                match key_event.code {
+                    KeyCode::Char('c') if key_event.modifiers == KeyModifiers::CONTROL => {
+                        self.exit()
+                    }
+                    KeyCode::Char('q') => self.exit(),
+                    KeyCode::Esc if self.writing.is_some() => self.cancel_write(),
+                    KeyCode::Esc => self.cancel_skip(&mut reader)?,
+                    _ => {
+                        self.load_view()?;
+                        let content =
+                            filter::content(&self.lines, self.filter.as_ref(), self.skip.as_ref());
+                        self.view.handle_events(&event, content)?;
+                    }
+                }
+            }
+            Event::Resize(w, h) => self.resize(w, h, stdout)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
     /// Handle global level events
     fn handle_global_events<T>(
         &mut self,
@@ -39,56 +136,157 @@ impl Pager {
         stdout: &mut std::io::Stdout,
     ) -> Result<(), Box<dyn std::error::Error>>
     where
-        T: std::io::BufRead,
+        T: std::io::BufRead + std::io::Seek,
     {
-        Ok(match event {
+        match event {
             // It's important to check that the event is a key-press event as
             // crossterm also emits key-release and repeat events on Windows.
             Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
                 match key_event.code {
+                    // Raw mode swallows the interrupt signal, so quit on Ctrl+C ourselves
+                    KeyCode::Char('c') if key_event.modifiers == KeyModifiers::CONTROL => {
+                        self.exit()
+                    }
                     KeyCode::End => self.go_to_end(reader)?,
-                    KeyCode::Enter => self.handle_command_line_submit(),
+                    KeyCode::Enter => self.handle_command_line_submit(reader, stdout)?,
+                    KeyCode::Char('n') => self.next_match(reader)?,
+                    KeyCode::Char('N') => self.prev_match()?,
+                    KeyCode::Char(']') => {
+                        if let Err(e) = self.next_file() {
+                            self.command_line.message = e;
+                        }
+                    }
+                    KeyCode::Char('[') => {
+                        if let Err(e) = self.prev_file() {
+                            self.command_line.message = e;
+                        }
+                    }
+                    KeyCode::Char('F') => self.toggle_follow(reader)?,
+                    KeyCode::Char('x') => self.toggle_hexdump()?,
                     KeyCode::Esc | KeyCode::Char('q') => self.exit(),
                     _ => {}
                 }
             }
             Event::Resize(w, h) => self.resize(w, h, stdout)?,
             _ => {}
-        })
+        }
+        Ok(())
     }
 
     /// Command-line submit event handlers
-    fn handle_command_line_submit(&mut self) {
+    fn handle_command_line_submit<T>(
+        &mut self,
+        reader: T,
+        stdout: &mut std::io::Stdout,
+    ) -> std::io::Result<()>
+    where
+        T: std::io::BufRead + std::io::Seek,
+    {
+        self.remember_input();
         match self.command_line.mode {
-            Mode::Search => self.search(),
-            Mode::Goto => self.goto(),
+            Mode::Search => self.search(reader)?,
+            Mode::Goto => self.goto(reader)?,
+            Mode::Filter => self.filter(&self.command_line.input.clone())?,
+            Mode::Command => self.command(reader, stdout)?,
+            Mode::Highlight => self.highlight(),
             _ => {}
         }
+        Ok(())
     }
 
-    /// Search for the given input
-    fn search(&mut self) {
-        self.view.search = self.command_line.input.clone()
+    /// Add the submitted search query or goto command to its history, and save the histories
+    fn remember_input(&mut self) {
+        let input = self.command_line.input.clone();
+        match self.command_line.mode {
+            Mode::Search => self.command_line.search_history.push(&input),
+            Mode::Goto => self.command_line.goto_history.push(&input),
+            _ => return,
+        }
+        if let Some(path) = &self.history_path {
+            // Failing to save the history shouldn't get in the way of paging
+            let _ = helpers::save_history(
+                path,
+                &mut self.command_line.search_history,
+                &mut self.command_line.goto_history,
+            );
+        }
     }
 
-    /// Jump to the provided line number and column
-    fn goto(&mut self) {
+    /// Jump to the provided line number and column, or to the line with the byte at a hex offset (like `0x1f00`)
+    fn goto<T>(&mut self, mut reader: T) -> std::io::Result<()>
+    where
+        T: std::io::BufRead + std::io::Seek,
+    {
         let input = self.command_line.input.clone();
         self.command_line.input.clear();
+        if let Some(offset) = helpers::parse_offset(&input) {
+            if self.hexdump.converted.is_set() {
+                self.command_line.message = String::from(hexdump::CONVERTED_MESSAGE);
+                return Ok(());
+            }
+            // The lines before it are skipped through if they're in a file, and it is gone to once they are
+            if !self.start_skip(&mut reader, offset, Then::Offset(offset))? {
+                self.go_to_offset(reader, offset)?;
+            }
+            return Ok(());
+        }
         let (row, col) = helpers::parse_row_and_col(&input);
-        self.view.scroll_row = row.unwrap_or(1).saturating_sub(1);
-        self.view.scroll_col = col.unwrap_or(1).saturating_sub(1);
+        let index = row.unwrap_or(1).saturating_sub(1);
+        // If the line is filtered out, go to where it would have been
+        self.view.scroll_row = self.content().position(index).unwrap_or_else(|row| row);
+        // The column counts characters, which may be displayed wider than one column (e.g. tabs)
+        let col = col.unwrap_or(1).saturating_sub(1);
+        self.load_line(index)?;
+        self.view.scroll_col = match self.lines.get(index) {
+            Some(line) => self.view.display_column(line, col),
+            None => col,
+        };
+        Ok(())
     }
 
-    /// Read and scroll to the end position.
-    /// Reads the entire file to the buffer.
-    fn go_to_end<T>(&mut self, reader: T) -> Result<(), Box<dyn std::error::Error>>
+    /// Go to the line (or the row of the hex dump) with the byte at the given offset, reading up to it
+    pub(super) fn go_to_offset<T>(&mut self, reader: T, offset: usize) -> std::io::Result<()>
     where
         T: std::io::BufRead,
+    {
+        self.read_to_offset(reader, offset)?;
+        let (index, within) = self.index_at(offset)?;
+        self.view.scroll_row = self.content().position(index).unwrap_or_else(|row| row);
+        // The rows of the hex dump fit in the view, but a line may have to be scrolled across to the byte
+        self.view.scroll_col = match self.lines.get(index) {
+            Some(line) if !self.hexdump.on => {
+                let chars = line.get(..within).map_or(within, |s| s.chars().count());
+                self.view.display_column(line, chars)
+            }
+            _ => 0,
+        };
+        Ok(())
+    }
+
+    /// Read and scroll to the end position.
+    /// Reads the entire file to the buffer. The lines of a file are skipped through instead of read,
+    /// showing its last lines in the meantime.
+    fn go_to_end<T>(&mut self, mut reader: T) -> Result<(), Box<dyn std::error::Error>>
+    where
+        T: std::io::BufRead + std::io::Seek,
     {
         self.read_all = true; // Set the flag to read all contents from the reader
-        self.buffer_lines(reader)?; // Read the contents
-        self.view.scroll_row = self.lines.len() - self.view.height + 1; // Update the scroll view position
+        if !self.start_skip(&mut reader, usize::MAX, Then::End)? {
+            self.buffer_lines(&mut reader)?; // Read the contents
+            self.scroll_to_end()?; // Update the scroll view position
+        }
+        Ok(())
+    }
+
+    /// Scroll the view so that the last row is at the bottom
+    pub(super) fn scroll_to_end(&mut self) -> std::io::Result<()> {
+        // The last rows have to be in memory to tell how many of them fit
+        let len = self.content().len();
+        for row in len.saturating_sub(self.view.height)..len {
+            self.load_row(row)?;
+        }
+        let content = filter::content(&self.lines, self.filter.as_ref(), self.skip.as_ref());
+        self.view.scroll_to_end(content);
         Ok(())
     }
 
@@ -0,0 +1,140 @@
+use super::{filter::Filter, hexdump::Hexdump, lines::Lines, Pager};
+use crate::helpers;
+
+/// The state of an input file that is kept apart from the others: its lines, and where the view was.
+/// The state of the file being shown lives in the Pager itself, and is swapped out when another file is shown.
+#[derive(Default)]
+pub(super) struct Buffer {
+    /// The name of the file shown in the command-line
+    name: String,
+    lines: Lines,
+    backing: helpers::Backing,
+    sgr: helpers::SgrIndex,
+    hexdump: Hexdump,
+    partial: Vec<u8>,
+    pub follow: helpers::Follow,
+    filter: Option<Filter>,
+    /// The filter as it was entered, to show in the command-line
+    filter_input: Option<String>,
+    pub read_all: bool,
+    pub scroll_row: usize,
+    pub scroll_col: usize,
+}
+
+impl Buffer {
+    pub fn new(
+        name: &str,
+        follow: helpers::Follow,
+        source: Option<helpers::Source>,
+        converted: helpers::Converted,
+        read_all: bool,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            backing: helpers::Backing::new(source),
+            hexdump: Hexdump::new(converted),
+            follow,
+            read_all,
+            ..Default::default()
+        }
+    }
+}
+
+impl Pager {
+    /// Show the file at the given index in place of the current one
+    pub(super) fn show_file(&mut self, index: usize) {
+        if index == self.current || index >= self.files.len() {
+            return;
+        }
+        self.swap_buffer(self.current);
+        self.current = index;
+        self.swap_buffer(index);
+
+        // The search pattern carries over to the new file, but its matches have to be found again
+        self.origin = None;
+        let search = self.view.search.take();
+        self.clear_search();
+        self.view.search = search;
+
+        self.update_file_indicator();
+        self.revision += 1;
+    }
+
+    /// Show the next file, if there is one
+    pub(super) fn next_file(&mut self) -> Result<(), String> {
+        if self.current + 1 >= self.files.len() {
+            return Err(String::from("No next file"));
+        }
+        self.show_file(self.current + 1);
+        Ok(())
+    }
+
+    /// Show the previous file, if there is one
+    pub(super) fn prev_file(&mut self) -> Result<(), String> {
+        if self.current == 0 {
+            return Err(String::from("No previous file"));
+        }
+        self.show_file(self.current - 1);
+        Ok(())
+    }
+
+    /// Exchange the state of the file being shown with the one kept in the buffer at the given index
+    pub(super) fn swap_buffer(&mut self, index: usize) {
+        let buffer = &mut self.files[index];
+        std::mem::swap(&mut self.lines, &mut buffer.lines);
+        std::mem::swap(&mut self.backing, &mut buffer.backing);
+        std::mem::swap(&mut self.sgr, &mut buffer.sgr);
+        std::mem::swap(&mut self.hexdump, &mut buffer.hexdump);
+        std::mem::swap(&mut self.partial, &mut buffer.partial);
+        std::mem::swap(&mut self.follow, &mut buffer.follow);
+        std::mem::swap(&mut self.filter, &mut buffer.filter);
+        std::mem::swap(&mut self.command_line.filter, &mut buffer.filter_input);
+        std::mem::swap(&mut self.read_all, &mut buffer.read_all);
+        std::mem::swap(&mut self.view.scroll_row, &mut buffer.scroll_row);
+        std::mem::swap(&mut self.view.scroll_col, &mut buffer.scroll_col);
+        self.view.hexdump = self.hexdump.on;
+        self.command_line.following = self.follow.is_on();
+    }
+
+    /// Show which of the files is being shown in the command-line, if there are several
+    pub(super) fn update_file_indicator(&mut self) {
+        self.command_line.file = match self.files.len() {
+            0 | 1 => None,
+            n => Some((self.files[self.current].name.clone(), self.current + 1, n)),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_keep_the_lines_and_the_scroll_position_of_each_file() {
+        let mut pager = Pager::init((80, 24));
+        pager
+            .with_file("a", Default::default(), None, Default::default())
+            .with_file("b", Default::default(), None, Default::default())
+            .with_offset(Some(3), Some(2));
+        pager.lines.push("in a".to_string(), 5);
+        pager.view.scroll_row = 5;
+
+        pager.next_file().unwrap();
+        assert_eq!(0, pager.lines.len());
+        assert_eq!((2, 1), (pager.view.scroll_row, pager.view.scroll_col));
+        assert_eq!(Some((String::from("b"), 2, 2)), pager.command_line.file);
+        assert_eq!(Err(String::from("No next file")), pager.next_file());
+        pager.lines.push("in b".to_string(), 5);
+        pager.view.scroll_row = 7;
+
+        pager.prev_file().unwrap();
+        assert_eq!(Some("in a"), pager.lines.get(0));
+        assert_eq!((5, 0), (pager.view.scroll_row, pager.view.scroll_col));
+        assert_eq!(Some((String::from("a"), 1, 2)), pager.command_line.file);
+        assert_eq!(Err(String::from("No previous file")), pager.prev_file());
+
+        pager.next_file().unwrap();
+        assert_eq!(Some("in b"), pager.lines.get(0));
+        assert_eq!((7, 1), (pager.view.scroll_row, pager.view.scroll_col));
+    }
+}
@@ -0,0 +1,168 @@
+use super::{
+    lines::{Lines, Skip},
+    search::SCAN_TIME_SLICE,
+    ui, Pager,
+};
+use crate::helpers;
+
+use std::time::Instant;
+
+/// Restricts the view to only the lines that match (or don't match) a pattern
+pub(super) struct Filter {
+    /// The pattern the lines are checked against
+    pattern: helpers::Pattern,
+    /// If true, only keep the lines that do _not_ match the pattern
+    invert: bool,
+    /// The indices of the buffered lines that pass the filter
+    rows: ui::Rows,
+    /// The number of buffered lines that have been checked against the filter so far
+    checked: usize,
+    /// The line to keep at the top of the view, once the lines up to it have been checked
+    top: Option<usize>,
+    /// Whether more lines are still being read to fill the view with those that pass, a slice of time at a time
+    reading: bool,
+}
+
+impl Filter {
+    /// The indices of the buffered lines that pass the filter
+    pub fn rows(&self) -> &ui::Rows {
+        &self.rows
+    }
+
+    /// Whether the line passes the filter
+    pub fn passes(&self, line: &str) -> bool {
+        self.pattern.is_match(line) != self.invert
+    }
+}
+
+impl Pager {
+    /// Filter the lines using the given pattern.
+    /// Prefixing the pattern with `!` only keeps the lines that do not match.
+    /// An empty input removes the filter.
+    pub(super) fn filter(&mut self, input: &str) -> std::io::Result<()> {
+        let (invert, query) = match input.strip_prefix('!') {
+            Some(query) => (true, query),
+            None => (false, input),
+        };
+
+        // Remember the line at the top of the viewport so that we can keep our place
+        let top = self.content().index(self.view.start());
+
+        if query.is_empty() {
+            self.filter = None;
+            self.command_line.filter = None;
+        } else {
+            match helpers::Pattern::new(query, self.command_line.case) {
+                Ok(pattern) => {
+                    self.filter = Some(Filter {
+                        pattern,
+                        invert,
+                        rows: ui::Rows::default(),
+                        checked: 0,
+                        top,
+                        reading: false,
+                    });
+                    self.command_line.filter = Some(input.to_string());
+                }
+                Err(e) => {
+                    self.command_line.message = format!("Invalid pattern: {e}");
+                    return Ok(());
+                }
+            }
+        }
+
+        self.revision += 1;
+
+        // Scroll to the row where the previous top line is (or would have been)
+        if let Some(top) = top.filter(|_| self.filter.is_none()) {
+            self.view.scroll_row = top;
+        }
+        self.update_filter()
+    }
+
+    /// Check any newly buffered lines against the filter, and update the progress shown in the command-line.
+    /// The lines that were dropped from memory are read back, a chunk at a time.
+    /// Checks for a slice of time, and carries on on the next iteration of the event loop.
+    pub(super) fn update_filter(&mut self) -> std::io::Result<()> {
+        let Some(filter) = &mut self.filter else {
+            self.command_line.filtering = None;
+            return Ok(());
+        };
+        let deadline = Instant::now() + SCAN_TIME_SLICE;
+        while filter.checked < self.lines.len() && Instant::now() < deadline {
+            self.lines.trim(self.backing.lost());
+            self.lines
+                .load(filter.checked, &self.backing, self.control)?;
+            while let Some(line) = self.lines.get(filter.checked) {
+                if filter.passes(line) {
+                    filter.rows.push(filter.checked);
+                }
+                filter.checked += 1;
+            }
+        }
+
+        // Scroll to the row where the previous top line is (or would have been), once it's been checked
+        if let Some(top) = filter.top {
+            if top < filter.checked || filter.checked == self.lines.len() {
+                self.view.scroll_row = filter.rows.position(top).unwrap_or_else(|row| row);
+                filter.top = None;
+            }
+        }
+
+        self.command_line.filtering =
+            (filter.checked < self.lines.len() || filter.reading).then_some(filter.checked);
+        Ok(())
+    }
+
+    /// Returns true if the lines are still being checked against the filter, or read to fill the view
+    pub(super) fn filtering(&self) -> bool {
+        self.checking_filter() || self.filter.as_ref().is_some_and(|filter| filter.reading)
+    }
+
+    /// Returns true if the buffered lines are still being checked against the filter
+    pub(super) fn checking_filter(&self) -> bool {
+        self.filter
+            .as_ref()
+            .is_some_and(|filter| filter.checked < self.lines.len())
+    }
+
+    /// Set whether more lines are still being read to fill the view with those that pass the filter
+    pub(super) fn set_filter_reading(&mut self, reading: bool) {
+        if let Some(filter) = &mut self.filter {
+            filter.reading = reading;
+        }
+    }
+
+    /// Stop checking the lines against the filter, and remove it
+    pub(super) fn cancel_filter(&mut self) -> std::io::Result<()> {
+        // Go back to the line that was at the top of the view, if the filter hasn't got to it yet
+        let top = self.filter.as_ref().and_then(|filter| filter.top);
+        self.filter("")?;
+        if let Some(top) = top {
+            self.view.scroll_row = top;
+        }
+        self.command_line.message = "Filter cancelled".to_string();
+        Ok(())
+    }
+
+    /// The contents to display in the view
+    pub(super) fn content(&self) -> ui::Content<'_> {
+        content(&self.lines, self.filter.as_ref(), self.skip.as_ref())
+    }
+}
+
+/// The lines that pass the filter, if one has been applied.
+/// While skipping through the lines to the end of a file, its last lines are shown instead.
+pub(super) fn content<'a>(
+    lines: &'a Lines,
+    filter: Option<&'a Filter>,
+    skip: Option<&'a Skip>,
+) -> ui::Content<'a> {
+    match skip.and_then(Skip::tail) {
+        Some(tail) => tail.content(),
+        None => ui::Content {
+            lines,
+            filter: filter.map(Filter::rows),
+        },
+    }
+}
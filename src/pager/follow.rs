@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use super::{lines::Then, Pager};
+
+/// How long to wait for events before checking the followed input for new contents
+pub const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+impl Pager {
+    /// Read everything new at the end of the followed input, skipping through it if there is a lot of it in a file.
+    /// If the view was showing the end, scroll along so that the new lines come into view.
+    pub(super) fn follow_input<T>(&mut self, mut reader: T) -> std::io::Result<()>
+    where
+        T: std::io::BufRead + std::io::Seek,
+    {
+        // The lines after those being skipped through are read once they've all been gone through
+        if self.skip.is_some() {
+            return Ok(());
+        }
+        let at_end = self.view.end(self.content()) >= self.content().len();
+        let then = if at_end { Then::End } else { Then::Stay };
+        if self.start_skip(&mut reader, usize::MAX, then)? {
+            return Ok(());
+        }
+        let mut count = 0;
+        loop {
+            let n = self.read_lines(&mut reader, self.view.height.max(1))?;
+            if n == 0 {
+                break;
+            }
+            count += n;
+        }
+        if at_end && count > 0 {
+            self.scroll_to_end()?;
+        }
+        Ok(())
+    }
+
+    /// Start or stop following the input. Starting jumps to the end.
+    pub(super) fn toggle_follow<T>(&mut self, reader: T) -> std::io::Result<()>
+    where
+        T: std::io::BufRead + std::io::Seek,
+    {
+        let follow = !self.follow.is_on();
+        self.follow.set(follow);
+        self.command_line.following = follow;
+        if follow {
+            self.follow_input(reader)?;
+            self.scroll_to_end()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+use super::{lines::Lines, Pager};
+use crate::helpers;
+
+/// Shown when the bytes of the file are asked for, but the input was converted from another encoding
+pub(super) const CONVERTED_MESSAGE: &str =
+    "No hex dump or byte offsets for input converted from another encoding";
+
+/// Shows the raw bytes of the input as a hex dump in place of the lines
+pub(super) struct Hexdump {
+    /// Whether the hex dump is shown instead of the lines
+    pub on: bool,
+    /// Whether the start of the input has been checked for binary data
+    detected: bool,
+    /// Set if the input is converted from another encoding, which leaves no bytes of the file to dump
+    pub converted: helpers::Converted,
+    /// The lines (or the rows, while the lines are shown) that are swapped out of the view
+    other: Lines,
+    /// The colours carried across the swapped out lines
+    other_sgr: helpers::SgrIndex,
+}
+
+impl Default for Hexdump {
+    fn default() -> Self {
+        Self {
+            on: false,
+            detected: false,
+            converted: helpers::Converted::default(),
+            other: Lines::rows(),
+            other_sgr: helpers::SgrIndex::default(),
+        }
+    }
+}
+
+impl Hexdump {
+    pub fn new(converted: helpers::Converted) -> Self {
+        Self {
+            converted,
+            ..Default::default()
+        }
+    }
+}
+
+impl Pager {
+    /// Show the input as a hex dump when it starts out looking like binary data
+    pub(super) fn detect_binary<T>(&mut self, mut reader: T) -> std::io::Result<()>
+    where
+        T: std::io::BufRead,
+    {
+        if self.hexdump.detected {
+            return Ok(());
+        }
+        let block = reader.fill_buf()?;
+        if block.is_empty() {
+            return Ok(()); // Nothing has come in yet
+        }
+        self.hexdump.detected = true;
+        // The hex dump may have been turned on before it was known that the input is converted
+        if self.hexdump.converted.is_set() {
+            if self.hexdump.on {
+                self.toggle_hexdump()?;
+            }
+            return Ok(());
+        }
+        if helpers::is_binary(block) {
+            self.toggle_hexdump()?;
+        }
+        Ok(())
+    }
+
+    /// The lines of the input and the colours carried across them, wherever they are while the hex dump is shown
+    pub(super) fn text(&mut self) -> (&mut Lines, &mut helpers::SgrIndex) {
+        match self.hexdump.on {
+            true => (&mut self.hexdump.other, &mut self.hexdump.other_sgr),
+            false => (&mut self.lines, &mut self.sgr),
+        }
+    }
+
+    /// Add the line to the lines, along with the raw bytes it was read from
+    pub(super) fn push_line(&mut self, line: String, raw: &[u8]) {
+        self.backing.push(raw);
+        let lost = self.backing.lost();
+        let (lines, sgr) = self.text();
+        sgr.push(&line);
+        lines.push(line, raw.len());
+        lines.trim(lost);
+    }
+
+    /// Bring the rows of the hex dump up to date with the bytes read.
+    /// A row is only added once it is full, unless `all` is set. Returns the number of rows added.
+    pub(super) fn update_hexdump(&mut self, all: bool) -> usize {
+        if !self.hexdump.on {
+            return 0;
+        }
+        let len = self.backing.len();
+        // A partial row stays until the bytes that complete it come in
+        let end = match all {
+            true => len,
+            false => (len - len % helpers::BYTES_PER_ROW).max(self.lines.end()),
+        };
+        if end == self.lines.end() {
+            return 0;
+        }
+        let before = self.lines.len();
+        self.lines.set_rows(end);
+        (self.lines.len() - before).max(1)
+    }
+
+    /// Switch between showing the lines and the hex dump, keeping the top of the view in place.
+    /// The filter and the search are cleared, as they were made for the other view.
+    /// There's no hex dump of input converted from another encoding.
+    pub(super) fn toggle_hexdump(&mut self) -> std::io::Result<()> {
+        if !self.hexdump.on && self.hexdump.converted.is_set() {
+            self.command_line.message = String::from(CONVERTED_MESSAGE);
+            return Ok(());
+        }
+        self.load_row(self.view.start())?;
+        let offset = self
+            .content()
+            .index(self.view.start())
+            .and_then(|index| self.lines.offset(index));
+
+        self.filter = None;
+        self.command_line.filter = None;
+        self.clear_search();
+
+        let hexdump = &mut self.hexdump;
+        hexdump.on = !hexdump.on;
+        std::mem::swap(&mut self.lines, &mut hexdump.other);
+        std::mem::swap(&mut self.sgr, &mut hexdump.other_sgr);
+        self.view.hexdump = hexdump.on;
+        self.update_hexdump(false);
+
+        self.view.scroll_col = 0;
+        self.view.scroll_row = match offset {
+            Some(offset) => self.index_at(offset)?.0,
+            None => 0,
+        };
+        self.revision += 1;
+        Ok(())
+    }
+
+    /// Read from the reader until the byte at the given offset has been buffered, or the input runs out
+    pub(super) fn read_to_offset<T>(&mut self, mut reader: T, offset: usize) -> std::io::Result<()>
+    where
+        T: std::io::BufRead,
+    {
+        let chunk = self.view.height.max(1);
+        while self.backing.len() <= offset && self.read_lines(&mut reader, chunk)? > 0 {}
+        Ok(())
+    }
+
+    /// Find the line (or the row of the hex dump) with the byte at the given offset.
+    /// Returns its index, and the byte offset within it.
+    pub(super) fn index_at(&mut self, offset: usize) -> std::io::Result<(usize, usize)> {
+        // Its chunk has to be in memory to find the line in it
+        let (index, _) = self.lines.index_at(offset);
+        self.load_line(index)?;
+        Ok(self.lines.index_at(offset))
+    }
+}
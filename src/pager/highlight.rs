@@ -0,0 +1,22 @@
+use super::Pager;
+use crate::helpers;
+
+impl Pager {
+    /// Manage the pinned highlights using the command-line input.
+    /// A pattern pins a new highlight, `-pattern` removes it again and a lone `-` removes all of them.
+    pub(super) fn highlight(&mut self) {
+        let input = std::mem::take(&mut self.command_line.input);
+        let highlights = &mut self.view.highlights;
+        match input.strip_prefix('-') {
+            Some("") => highlights.clear(),
+            Some(query) => highlights.retain(|h| h.pattern.as_str() != query),
+            None if input.is_empty() => {}
+            None => match helpers::Pattern::new(&input, self.command_line.case) {
+                Ok(pattern) if highlights.iter().any(|h| h.pattern == pattern) => {}
+                Ok(pattern) => highlights.push(helpers::Highlight::new(pattern, highlights)),
+                Err(e) => self.command_line.message = format!("Invalid pattern: {e}"),
+            },
+        }
+        self.command_line.highlights = self.view.highlights.clone();
+    }
+}
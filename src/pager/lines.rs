@@ -0,0 +1,725 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Seek, SeekFrom};
+use std::time::Instant;
+
+use super::{search::SCAN_TIME_SLICE, ui, Pager};
+use crate::helpers;
+
+/// The number of lines in a chunk. The chunks line up with the checkpoints of the [helpers::SgrIndex]
+pub(super) const CHUNK_LEN: usize = helpers::CHECKPOINT_INTERVAL;
+
+/// The number of chunks kept in memory when they are trimmed
+const CACHED_CHUNKS: usize = 64;
+
+/// Skipping through the file by its line breaks, rather than reading the lines, only pays off past this many bytes
+const SKIP_THRESHOLD: usize = 1024 * 1024;
+
+/// The size of the blocks the file is scanned for line breaks in
+const SCAN_BLOCK_SIZE: usize = 1024 * 1024;
+
+/// Shown in place of the lines that can't be read back, as the file was truncated since they were read
+const LOST_LINE: &str = "--- lost when the file was truncated ---";
+
+/// A chunk of lines kept in memory
+#[derive(Default)]
+struct Chunk {
+    lines: Vec<String>,
+    /// The offset of the first byte of each of the lines
+    starts: Vec<usize>,
+    /// When the chunk was last used
+    used: u64,
+    /// Whether some of its lines were lost when the file was truncated
+    lost: bool,
+}
+
+/// What to do once the lines being skipped through have all been gone through
+#[derive(Clone, Copy)]
+pub(super) enum Then {
+    /// Carry on reading the lines after them
+    Stay,
+    /// Read the rest of the input and scroll to its end
+    End,
+    /// Go to the line with the byte at the offset
+    Offset(usize),
+}
+
+/// Going through the lines of a file by their line breaks, a slice of time at a time, to skip to an offset in it
+pub(super) struct Skip {
+    /// Where the lines being skipped start
+    start: usize,
+    /// The offset to go through the lines up to
+    target: usize,
+    /// How far the file has been gone through
+    pos: usize,
+    /// The start of the line that runs on from the last block, if it has escape codes
+    carry: Vec<u8>,
+    then: Then,
+    /// Where the view was before it showed the tail, to go back to if the skipping is cancelled
+    scroll_row: usize,
+    /// The last lines of the file, shown in the meantime when skipping to its end
+    tail: Option<Tail>,
+}
+
+impl Skip {
+    /// The last lines of the file, if they're shown in the meantime
+    pub fn tail(&self) -> Option<&Tail> {
+        self.tail.as_ref()
+    }
+
+    /// Account for the lines that end in the next block of the file, and the colours they carry over
+    fn scan(
+        &mut self,
+        bytes: &[u8],
+        lines: &mut Lines,
+        sgr: &mut helpers::SgrIndex,
+        colours: bool,
+    ) {
+        let breaks: Vec<usize> = memchr::memchr_iter(b'\n', bytes).collect();
+        if colours && (!self.carry.is_empty() || memchr::memchr(0x1b, bytes).is_some()) {
+            let mut line_start = 0;
+            for &i in &breaks {
+                self.carry.extend_from_slice(&bytes[line_start..i]);
+                sgr.push(&String::from_utf8_lossy(&self.carry));
+                self.carry.clear();
+                line_start = i + 1;
+            }
+            self.carry.extend_from_slice(&bytes[line_start..]);
+            if memchr::memchr(0x1b, &self.carry).is_none() {
+                self.carry.clear();
+            }
+        } else {
+            breaks.iter().for_each(|_| sgr.push(""));
+        }
+        let pos = self.pos;
+        lines.skip(breaks.iter().map(|i| pos + i + 1));
+        self.pos += bytes.len();
+    }
+}
+
+/// The last lines of a file, shown before the lines leading up to them have been counted.
+/// Their line numbers aren't known until then, and the colours they carry over start afresh.
+pub(super) struct Tail {
+    lines: Lines,
+    sgr: helpers::SgrIndex,
+    /// The indices of the lines that pass the filter, if one has been applied
+    filter: Option<ui::Rows>,
+}
+
+impl Tail {
+    /// The contents to display in the view
+    pub fn content(&self) -> ui::Content<'_> {
+        ui::Content {
+            lines: &self.lines,
+            filter: self.filter.as_ref(),
+        }
+    }
+}
+
+/// The lines of the input (or the rows of its hex dump), kept in memory a chunk at a time.
+/// Only the chunks in use stay in memory: the others are dropped, and read back from the [helpers::Backing]
+/// when they're needed again. The offset of the first line of each chunk is all that's kept of them.
+#[derive(Default)]
+pub(super) struct Lines {
+    /// The number of lines
+    len: usize,
+    /// The offset of the first byte of each chunk
+    starts: Vec<usize>,
+    /// The offset after the last byte of the lines
+    end: usize,
+    /// Whether these are the rows of a hex dump, which start every `BYTES_PER_ROW` bytes
+    rows: bool,
+    /// The chunks kept in memory, by their index
+    chunks: HashMap<usize, Chunk>,
+    /// Counts the uses of the chunks, to tell which was used the longest ago
+    clock: u64,
+}
+
+impl Lines {
+    /// The rows of a hex dump
+    pub fn rows() -> Self {
+        Self {
+            rows: true,
+            ..Default::default()
+        }
+    }
+
+    /// The number of lines
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The offset after the last byte of the lines
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// The line at the given index, if its chunk is in memory
+    pub fn get(&self, index: usize) -> Option<&str> {
+        let chunk = self.chunks.get(&(index / CHUNK_LEN))?;
+        chunk.lines.get(index % CHUNK_LEN).map(String::as_str)
+    }
+
+    /// The lines before the one at the given index, back to the start of its chunk
+    pub fn leading(&self, index: usize) -> &[String] {
+        match self.chunks.get(&(index / CHUNK_LEN)) {
+            Some(chunk) => &chunk.lines[..(index % CHUNK_LEN).min(chunk.lines.len())],
+            None => &[],
+        }
+    }
+
+    /// The offset of the first byte of the line at the given index, if its chunk is in memory
+    pub fn offset(&self, index: usize) -> Option<usize> {
+        if self.rows {
+            return Some(index * helpers::BYTES_PER_ROW);
+        }
+        let chunk = self.chunks.get(&(index / CHUNK_LEN))?;
+        chunk.starts.get(index % CHUNK_LEN).copied()
+    }
+
+    /// The offsets of the raw bytes the line at the given index was read from, if its chunk is in memory
+    pub fn range(&self, index: usize) -> Option<std::ops::Range<usize>> {
+        if index >= self.len {
+            return None;
+        }
+        let start = self.offset(index)?;
+        let end = match self.rows {
+            true => start + helpers::BYTES_PER_ROW,
+            false => {
+                let chunk = &self.chunks.get(&(index / CHUNK_LEN))?;
+                let next = chunk.starts.get(index % CHUNK_LEN + 1);
+                next.or(self.starts.get(index / CHUNK_LEN + 1))
+                    .copied()
+                    .unwrap_or(self.end)
+            }
+        };
+        Some(start..end.min(self.end))
+    }
+
+    /// Find the line with the byte at the given offset. Returns its index, and the byte offset within it.
+    /// If the chunk it's in isn't in memory, returns the first line of the chunk instead.
+    pub fn index_at(&self, offset: usize) -> (usize, usize) {
+        if self.rows {
+            let index = (offset / helpers::BYTES_PER_ROW).min(self.len.saturating_sub(1));
+            return (index, offset - index * helpers::BYTES_PER_ROW);
+        }
+        let chunk = self
+            .starts
+            .partition_point(|&start| start <= offset)
+            .saturating_sub(1);
+        let starts = match self.chunks.get(&chunk) {
+            Some(loaded) => &loaded.starts[..],
+            None => self.starts.get(chunk..chunk + 1).unwrap_or_default(),
+        };
+        let within = starts
+            .partition_point(|&start| start <= offset)
+            .saturating_sub(1);
+        let start = starts.get(within).copied().unwrap_or(0).min(offset);
+        (chunk * CHUNK_LEN + within, offset - start)
+    }
+
+    /// Add a line, along with the length of the raw bytes it was read from
+    pub fn push(&mut self, line: String, len: usize) {
+        let index = self.len / CHUNK_LEN;
+        if self.len.is_multiple_of(CHUNK_LEN) {
+            self.starts.push(self.end);
+            self.clock += 1;
+            let used = self.clock;
+            self.chunks.insert(
+                index,
+                Chunk {
+                    used,
+                    ..Default::default()
+                },
+            );
+        }
+        // If the chunk was dropped, the line is read back with the rest of it
+        if let Some(chunk) = self.chunks.get_mut(&index) {
+            chunk.lines.push(line);
+            chunk.starts.push(self.end);
+        }
+        self.len += 1;
+        self.end += len;
+    }
+
+    /// Account for the lines ending at the given offsets, without keeping them.
+    /// They're read back from the backing when they're needed.
+    pub fn skip(&mut self, ends: impl IntoIterator<Item = usize>) {
+        // The chunk in progress is missing the lines, so it is read back in full when needed
+        if !self.len.is_multiple_of(CHUNK_LEN) {
+            self.chunks.remove(&(self.len / CHUNK_LEN));
+        }
+        for end in ends {
+            if self.len.is_multiple_of(CHUNK_LEN) {
+                self.starts.push(self.end);
+            }
+            self.len += 1;
+            self.end = end;
+        }
+    }
+
+    /// Set the rows of the hex dump to those of the bytes up to the given offset
+    pub fn set_rows(&mut self, end: usize) {
+        // A partial row at the end is made again with the bytes added to it
+        if !self.end.is_multiple_of(helpers::BYTES_PER_ROW) && self.len > 0 {
+            self.chunks.remove(&((self.len - 1) / CHUNK_LEN));
+        }
+        self.len = end.div_ceil(helpers::BYTES_PER_ROW);
+        self.end = end;
+    }
+
+    /// Make sure the chunk with the line at the given index is in memory, reading it back from the backing if need be
+    pub fn load(
+        &mut self,
+        index: usize,
+        backing: &helpers::Backing,
+        control: helpers::ControlChars,
+    ) -> std::io::Result<()> {
+        if index >= self.len {
+            return Ok(());
+        }
+        self.clock += 1;
+        let chunk = index / CHUNK_LEN;
+        let count = CHUNK_LEN.min(self.len - chunk * CHUNK_LEN);
+        // A chunk of rows made before the rest of its bytes came in is made again
+        if let Some(loaded) = self.chunks.get_mut(&chunk) {
+            if loaded.lines.len() >= count {
+                loaded.used = self.clock;
+                return Ok(());
+            }
+        }
+
+        let mut loaded = Chunk {
+            used: self.clock,
+            ..Default::default()
+        };
+        if self.rows {
+            let start = chunk * CHUNK_LEN * helpers::BYTES_PER_ROW;
+            let len = (count * helpers::BYTES_PER_ROW).min(self.end - start);
+            let bytes = backing.read(start, len)?;
+            // The bytes that were lost start the rows off, up to the first row that's all there
+            let lost = backing.lost().clamp(start, start + len) - start;
+            let first = lost.div_ceil(helpers::BYTES_PER_ROW);
+            for i in 0..first {
+                loaded.lines.push(String::from(LOST_LINE));
+                loaded.starts.push(start + i * helpers::BYTES_PER_ROW);
+            }
+            let skip = (first * helpers::BYTES_PER_ROW - lost).min(bytes.len());
+            for (i, row) in bytes[skip..].chunks(helpers::BYTES_PER_ROW).enumerate() {
+                let offset = start + (first + i) * helpers::BYTES_PER_ROW;
+                loaded.lines.push(helpers::hexdump_row(offset, row));
+                loaded.starts.push(offset);
+            }
+            loaded.lost = first > 0;
+        } else {
+            let start = self.starts[chunk];
+            let end = self.starts.get(chunk + 1).copied().unwrap_or(self.end);
+            let bytes = backing.read(start, end - start)?;
+            let raws: Vec<&[u8]> = bytes.split_inclusive(|&b| b == b'\n').collect();
+            // The lines that were lost are the ones missing from before those that could be read back
+            let lost = backing.lost().clamp(start, end);
+            let missing = match lost > start {
+                true => count.saturating_sub(raws.len()),
+                false => 0,
+            };
+            for _ in 0..missing {
+                loaded.lines.push(String::from(LOST_LINE));
+                loaded.starts.push(start);
+            }
+            let mut offset = lost;
+            for raw in raws.into_iter().take(count - missing) {
+                loaded.lines.push(decode(raw, control));
+                loaded.starts.push(offset);
+                offset += raw.len();
+            }
+            loaded.lost = missing > 0;
+        }
+        // The file may have been cut short since, but the lines are still accounted for
+        while loaded.lines.len() < count {
+            loaded.lines.push(String::new());
+            loaded
+                .starts
+                .push(loaded.starts.last().copied().unwrap_or(0));
+        }
+        self.chunks.insert(chunk, loaded);
+        Ok(())
+    }
+
+    /// Drop the chunks used the longest ago from memory, keeping only so many.
+    /// The chunks with lines before the given offset are kept as well, as they can't be read back since the file was truncated.
+    pub fn trim(&mut self, lost: usize) {
+        if self.chunks.len() <= CACHED_CHUNKS {
+            return;
+        }
+        let mut used: Vec<u64> = self.chunks.values().map(|chunk| chunk.used).collect();
+        used.sort_unstable();
+        let oldest = used[used.len() - CACHED_CHUNKS];
+        self.chunks.retain(|_, chunk| {
+            let kept = !chunk.lost && chunk.starts.first().is_some_and(|&start| start < lost);
+            chunk.used >= oldest || kept
+        });
+    }
+}
+
+/// Turn the raw bytes of a line into the line to show, without its line break
+pub(super) fn decode(raw: &[u8], control: helpers::ControlChars) -> String {
+    let line = match raw.strip_suffix(b"\n") {
+        Some(l) => l.strip_suffix(b"\r").unwrap_or(l),
+        None => raw,
+    };
+    // Invalid UTF-8 (e.g. binary data) is shown as replacement characters rather than giving up on the input
+    helpers::sanitize(&String::from_utf8_lossy(line), control)
+}
+
+impl Pager {
+    /// Make sure the rows in and around the view are in memory, to be shown or scrolled to
+    pub(super) fn load_view(&mut self) -> std::io::Result<()> {
+        // The last lines of the file are all in memory while they're shown
+        if self.tail().is_some() {
+            return Ok(());
+        }
+        self.lines.trim(self.backing.lost());
+        let height = self.view.height.max(1);
+        let start = self.view.start().saturating_sub(height);
+        for row in start..self.view.start() + 2 * height {
+            if let Some(index) = self.content().index(row) {
+                self.lines.load(index, &self.backing, self.control)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Make sure the line shown at the given row is in memory
+    pub(super) fn load_row(&mut self, row: usize) -> std::io::Result<()> {
+        if self.tail().is_some() {
+            return Ok(());
+        }
+        match self.content().index(row) {
+            Some(index) => self.load_line(index),
+            None => Ok(()),
+        }
+    }
+
+    /// Make sure the line at the given index is in memory, dropping the chunks used the longest ago to make room
+    pub(super) fn load_line(&mut self, index: usize) -> std::io::Result<()> {
+        if self.lines.get(index).is_none() {
+            self.lines.trim(self.backing.lost());
+            self.lines.load(index, &self.backing, self.control)?;
+        }
+        Ok(())
+    }
+
+    /// Start going through the lines of the file up to the given offset by only looking for their line breaks,
+    /// so that they don't all have to be read. They're read back from the file when they're needed,
+    /// and the reader carries on after them. Only files that are read as they are can be skipped through.
+    /// The lines are gone through a slice of time at a time, and `then` is carried out once they all are.
+    /// Returns false if there aren't enough lines for it to pay off, in which case they're left to be read.
+    pub(super) fn start_skip<T>(
+        &mut self,
+        reader: &mut T,
+        offset: usize,
+        then: Then,
+    ) -> std::io::Result<bool>
+    where
+        T: BufRead + Seek,
+    {
+        let Some(source) = self.backing.source().cloned() else {
+            return Ok(false);
+        };
+        let start = self.backing.len();
+        let len = source.len()?.try_into().unwrap_or(usize::MAX);
+        let target = offset.min(len);
+        if self.skip.is_some() || target < start.saturating_add(SKIP_THRESHOLD) {
+            return Ok(false);
+        }
+        self.detect_binary(&mut *reader)?;
+
+        // Show the end of the file straight away, while the lines before it are being gone through
+        let tail = match then {
+            Then::End => Some(self.read_tail(&source, start, target)?),
+            _ => None,
+        };
+        self.view.tail = tail.is_some();
+        self.skip = Some(Skip {
+            start,
+            target,
+            pos: start,
+            carry: Vec::new(),
+            then,
+            scroll_row: self.view.scroll_row,
+            tail,
+        });
+        self.scroll_to_end_of_tail();
+        self.continue_skip(reader)?;
+        Ok(true)
+    }
+
+    /// Go through the lines being skipped for a slice of time, and carry on after them once they've all been
+    pub(super) fn continue_skip<T>(&mut self, reader: &mut T) -> std::io::Result<()>
+    where
+        T: BufRead + Seek,
+    {
+        let Some(mut skip) = self.skip.take() else {
+            return Ok(());
+        };
+        // Once the file has been rotated, the lines gone through so far are all that can be skipped
+        let Some(source) = self.backing.source().cloned() else {
+            return self.finish_skip(reader, skip, true);
+        };
+
+        // Colours are carried over from one line to the next, so the lines with escape codes are gone through in full
+        let colours = self.control != helpers::ControlChars::Caret;
+        let deadline = Instant::now() + SCAN_TIME_SLICE;
+        let mut block = vec![0; SCAN_BLOCK_SIZE];
+        while skip.pos < skip.target && Instant::now() < deadline {
+            let size = SCAN_BLOCK_SIZE.min(skip.target - skip.pos);
+            let n = source.read_at(skip.pos as u64, &mut block[..size])?;
+            if n == 0 {
+                break; // The file was cut short
+            }
+            let (lines, sgr) = self.text();
+            skip.scan(&block[..n], lines, sgr, colours);
+            let end = lines.end();
+            self.backing.skip_to(end);
+            self.update_filter()?;
+            if n < size {
+                break;
+            }
+        }
+
+        if skip.pos < skip.target && Instant::now() >= deadline {
+            let done = (skip.pos - skip.start) * 100 / (skip.target - skip.start);
+            self.command_line.skipping = Some(done);
+            self.skip = Some(skip);
+            return Ok(());
+        }
+        self.finish_skip(reader, skip, true)
+    }
+
+    /// Stop skipping through the lines, keeping those gone through so far.
+    /// The view goes back to where it was, and the input stops being read to its end.
+    pub(super) fn cancel_skip<T>(&mut self, reader: &mut T) -> std::io::Result<()>
+    where
+        T: BufRead + Seek,
+    {
+        let Some(skip) = self.skip.take() else {
+            return Ok(());
+        };
+        if skip.tail.is_some() {
+            self.view.scroll_row = skip.scroll_row;
+        }
+        self.read_all = false;
+        self.follow.set(false);
+        self.command_line.following = false;
+        self.command_line.message = String::from("Skipping cancelled");
+        self.finish_skip(reader, skip, false)
+    }
+
+    /// Carry on reading after the lines gone through, including the one that was on its way.
+    /// Then do what was to be done once they all were, unless the skipping was cancelled.
+    fn finish_skip<T>(&mut self, reader: &mut T, skip: Skip, done: bool) -> std::io::Result<()>
+    where
+        T: BufRead + Seek,
+    {
+        let end = self.text().0.end();
+        self.partial.clear();
+        reader.seek(SeekFrom::Start(end as u64))?;
+        self.update_hexdump(false);
+        self.view.tail = false;
+        self.command_line.skipping = None;
+        self.revision += 1;
+        match skip.then {
+            _ if !done => {}
+            Then::Stay => {}
+            Then::End => {
+                self.buffer_lines(reader)?;
+                self.scroll_to_end()?;
+            }
+            Then::Offset(offset) => self.go_to_offset(reader, offset)?,
+        }
+        Ok(())
+    }
+
+    /// Read the last lines of the file before the target (or the last rows of its hex dump), to fill the view with.
+    /// Only goes back as far as the start of the lines being skipped.
+    fn read_tail(
+        &self,
+        source: &helpers::Source,
+        start: usize,
+        target: usize,
+    ) -> std::io::Result<Tail> {
+        let count = 2 * self.view.height.max(1);
+        let mut tail = Tail {
+            lines: Lines::default(),
+            sgr: helpers::SgrIndex::default(),
+            filter: None,
+        };
+        if self.hexdump.on {
+            let from = target
+                .saturating_sub(count * helpers::BYTES_PER_ROW)
+                .max(start);
+            let from = from - from % helpers::BYTES_PER_ROW;
+            let mut bytes = vec![0; target - from];
+            let n = source.read_at(from as u64, &mut bytes)?;
+            for (i, row) in bytes[..n].chunks(helpers::BYTES_PER_ROW).enumerate() {
+                let row = helpers::hexdump_row(from + i * helpers::BYTES_PER_ROW, row);
+                tail.sgr.push(&row);
+                tail.lines.push(row, helpers::BYTES_PER_ROW);
+            }
+        } else {
+            let from = target.saturating_sub(SCAN_BLOCK_SIZE).max(start);
+            let mut bytes = vec![0; target - from];
+            let n = source.read_at(from as u64, &mut bytes)?;
+            let raws: Vec<&[u8]> = bytes[..n].split_inclusive(|&b| b == b'\n').collect();
+            // The first line may have started before the bytes that were read
+            let first = usize::from(from > start && raws.len() > 1);
+            for raw in &raws[first.max(raws.len().saturating_sub(count))..] {
+                let line = decode(raw, self.control);
+                tail.sgr.push(&line);
+                tail.lines.push(line, raw.len());
+            }
+        }
+        if let Some(filter) = &self.filter {
+            let rows = (0..tail.lines.len())
+                .filter(|&i| tail.lines.get(i).is_some_and(|line| filter.passes(line)))
+                .collect();
+            tail.filter = Some(rows);
+        }
+        Ok(tail)
+    }
+
+    /// The last lines of the file, if they're shown while the lines before them are being skipped through
+    pub(super) fn tail(&self) -> Option<&Tail> {
+        self.skip.as_ref().and_then(Skip::tail)
+    }
+
+    /// Scroll the view to the end of the last lines of the file, if they're shown
+    fn scroll_to_end_of_tail(&mut self) {
+        if let Some(tail) = self.skip.as_ref().and_then(Skip::tail) {
+            self.view.scroll_to_end(tail.content());
+        }
+    }
+
+    /// The colours carried across the lines shown in the view
+    pub(super) fn colours(&self) -> &helpers::SgrIndex {
+        match self.tail() {
+            Some(tail) => &tail.sgr,
+            None => &self.sgr,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(backing: &mut helpers::Backing, count: usize) -> Lines {
+        let mut lines = Lines::default();
+        for i in 0..count {
+            let raw = format!("line {i}\n");
+            backing.push(raw.as_bytes());
+            lines.push(
+                decode(raw.as_bytes(), helpers::ControlChars::Caret),
+                raw.len(),
+            );
+        }
+        lines
+    }
+
+    #[test]
+    fn should_read_dropped_chunks_back() {
+        let mut backing = helpers::Backing::default();
+        let mut lines = lines(&mut backing, CHUNK_LEN * (CACHED_CHUNKS + 2));
+        lines.trim(0);
+        assert_eq!(None, lines.get(0));
+        assert_eq!(Some("line 512"), lines.get(512));
+
+        lines
+            .load(5, &backing, helpers::ControlChars::Caret)
+            .unwrap();
+        assert_eq!(Some("line 5"), lines.get(5));
+        assert_eq!(Some(35), lines.offset(5));
+        assert_eq!(Some(35..42), lines.range(5));
+        assert_eq!((5, 2), lines.index_at(37));
+    }
+
+    #[test]
+    fn should_account_for_skipped_lines() {
+        let mut backing = helpers::Backing::default();
+        let mut lines = lines(&mut backing, 3);
+        backing.push(b"four\nfive\n");
+        lines.skip([26, 31]);
+        assert_eq!(5, lines.len());
+        assert_eq!(None, lines.get(0));
+
+        lines
+            .load(4, &backing, helpers::ControlChars::Caret)
+            .unwrap();
+        assert_eq!(Some("line 0"), lines.get(0));
+        assert_eq!(Some("five"), lines.get(4));
+        assert_eq!(Some(21..26), lines.range(3));
+    }
+
+    #[test]
+    fn should_make_the_rows_of_the_hex_dump() {
+        let mut backing = helpers::Backing::default();
+        backing.push(b"0123456789abcdef0123");
+        let mut rows = Lines::rows();
+        rows.set_rows(20);
+        rows.load(1, &backing, helpers::ControlChars::Caret)
+            .unwrap();
+        assert_eq!(2, rows.len());
+        assert!(rows.get(1).unwrap().ends_with("|0123|"));
+
+        backing.push(b"45");
+        rows.set_rows(22);
+        rows.load(1, &backing, helpers::ControlChars::Caret)
+            .unwrap();
+        assert!(rows.get(1).unwrap().ends_with("|012345|"));
+    }
+
+    #[test]
+    fn should_add_the_rows_of_the_bytes_that_come_in_later() {
+        let mut backing = helpers::Backing::default();
+        backing.push(&[b'a'; 32]);
+        let mut rows = Lines::rows();
+        rows.set_rows(32);
+        rows.load(0, &backing, helpers::ControlChars::Caret)
+            .unwrap();
+
+        backing.push(&[b'b'; 32]);
+        rows.set_rows(64);
+        rows.load(3, &backing, helpers::ControlChars::Caret)
+            .unwrap();
+        assert_eq!(4, rows.len());
+        assert!(rows.get(3).unwrap().ends_with("|bbbbbbbbbbbbbbbb|"));
+    }
+
+    #[test]
+    fn should_not_read_back_lines_lost_when_the_file_was_truncated() {
+        let path = std::env::temp_dir().join(format!("scan-test-lost-{}", std::process::id()));
+        let count = CHUNK_LEN * (CACHED_CHUNKS + 2);
+        let contents: String = (0..count).map(|i| format!("line {i}\n")).collect();
+        std::fs::write(&path, &contents).unwrap();
+        let reader = helpers::Reader::file(&path, helpers::Encoding::Auto).unwrap();
+        let mut backing = helpers::Backing::new(reader.source());
+        let mut lines = lines(&mut backing, count);
+        lines.trim(backing.lost());
+        // The second chunk is the one used the longest ago
+        for chunk in 1..count / CHUNK_LEN {
+            lines
+                .load(chunk * CHUNK_LEN, &backing, helpers::ControlChars::Caret)
+                .unwrap();
+        }
+
+        std::fs::write(&path, "new\n").unwrap();
+        lines
+            .load(0, &backing, helpers::ControlChars::Caret)
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(Some(LOST_LINE), lines.get(0));
+
+        // The lines still in memory are kept, as they can't be read back either
+        lines.trim(backing.lost());
+        assert_eq!(Some("line 256"), lines.get(CHUNK_LEN));
+    }
+}
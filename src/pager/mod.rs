@@ -1,13 +1,46 @@
-use crate::helpers::layout;
+use std::time::Instant;
 
+use crate::helpers::{self, layout};
+
+mod command;
 mod events;
+mod files;
+mod filter;
+mod follow;
+mod hexdump;
+mod highlight;
+mod lines;
 mod render;
+mod search;
 mod ui;
 
 #[derive(Default)]
 pub struct Pager {
+    /// The input files, with the state of those not being shown
+    files: Vec<files::Buffer>,
+    /// The index of the file being shown
+    current: usize,
+
     /// The collection of buffered lines
-    lines: Vec<String>,
+    lines: lines::Lines,
+
+    /// The raw contents of the input, that the lines are read back from
+    backing: helpers::Backing,
+
+    /// How the control characters in the contents are shown
+    control: helpers::ControlChars,
+
+    /// The colours carried over from one line to the next by their escape codes
+    sgr: helpers::SgrIndex,
+
+    /// Shows the raw contents of the input as a hex dump
+    hexdump: hexdump::Hexdump,
+
+    /// The start of a line that is still being written to the followed input
+    partial: Vec<u8>,
+
+    /// Keeps the reader looking for new contents at the end of the input
+    follow: helpers::Follow,
 
     /// The current Pager's view
     view: ui::View,
@@ -15,9 +48,33 @@ pub struct Pager {
     /// The application's command line
     command_line: ui::CommandLine,
 
+    /// The filter restricting which lines are shown, if any
+    filter: Option<filter::Filter>,
+
+    /// Where the view was before the current search, so that it can be restored if cancelled
+    origin: Option<search::Origin>,
+
+    /// The forward search in progress, if any
+    scan: Option<search::Scan>,
+
+    /// The skipping through the lines of a file in progress, if any
+    skip: Option<lines::Skip>,
+
+    /// The writing of the lines to a file in progress, if any
+    writing: Option<command::Writing>,
+
+    /// The tally of search matches in the buffered lines
+    tally: search::Tally,
+
     /// Stores a snapshot of the previously rendered view.
     prev: PreviousFrame,
 
+    /// Where the search and goto histories are saved, if anywhere
+    history_path: Option<std::path::PathBuf>,
+
+    /// Incremented whenever the contents change so that the view knows to re-render
+    revision: usize,
+
     // Should read the entire file in one go
     read_all: bool,
 
@@ -34,6 +91,7 @@ pub struct Pager {
 struct PreviousFrame {
     view: ui::View,
     command_line: ui::CommandLine,
+    revision: usize,
 }
 
 impl Pager {
@@ -58,38 +116,143 @@ impl Pager {
         self
     }
 
-    /// Set the starting scroll offsets
+    /// Enable/Disable wrapping long lines
+    pub fn with_wrap(&mut self, yes: bool) -> &mut Self {
+        self.view.wrap = yes;
+        self
+    }
+
+    /// Set the number of columns between tab stops
+    pub fn with_tabs(&mut self, tabstop: usize) -> &mut Self {
+        self.view.tabstop = tabstop;
+        self
+    }
+
+    /// Set how the control characters in the contents are shown
+    pub fn with_control(&mut self, control: helpers::ControlChars) -> &mut Self {
+        self.control = control;
+        self
+    }
+
+    /// Set how search patterns treat upper and lower case letters
+    pub fn with_case(&mut self, case: helpers::Case) -> &mut Self {
+        self.command_line.case = case;
+        self
+    }
+
+    /// Add an input file to page through, along with the switch for following it, the file its lines
+    /// can be read back from, if any, and the flag telling if it is converted from another encoding.
+    /// The first file added is the one shown at the start.
+    pub fn with_file(
+        &mut self,
+        name: &str,
+        follow: helpers::Follow,
+        source: Option<helpers::Source>,
+        converted: helpers::Converted,
+    ) -> &mut Self {
+        self.files.push(files::Buffer::new(
+            name,
+            follow,
+            source,
+            converted,
+            self.read_all,
+        ));
+        if self.files.len() == 1 {
+            self.swap_buffer(0);
+        }
+        self.update_file_indicator();
+        self
+    }
+
+    /// Set the starting scroll offsets of the file added last
     pub fn with_offset(&mut self, row: Option<usize>, col: Option<usize>) -> &mut Self {
-        self.view.scroll_row = row.unwrap_or(0).saturating_sub(1);
-        self.view.scroll_col = col.unwrap_or(0).saturating_sub(1);
+        let row = row.unwrap_or(0).saturating_sub(1);
+        let col = col.unwrap_or(0).saturating_sub(1);
+        // The first file is already being shown, while the others are kept in their buffers
+        if self.files.len() > 1 {
+            if let Some(buffer) = self.files.last_mut() {
+                buffer.scroll_row = row;
+                buffer.scroll_col = col;
+            }
+        } else {
+            self.view.scroll_row = row;
+            self.view.scroll_col = col;
+        }
+        self
+    }
+
+    /// Load the search and goto histories from, and save them to, the given file
+    pub fn with_history(&mut self, path: Option<std::path::PathBuf>) -> &mut Self {
+        // A missing or unreadable history file just means there is no history yet
+        if let Some((search, goto)) = path.as_deref().and_then(|p| helpers::load_history(p).ok()) {
+            self.command_line.search_history = search;
+            self.command_line.goto_history = goto;
+        }
+        self.history_path = path;
+        self
+    }
+
+    /// Start following the input files right away, or not
+    pub fn with_follow(&mut self, yes: bool) -> &mut Self {
+        self.follow.set(yes);
+        self.files.iter().for_each(|buffer| buffer.follow.set(yes));
+        self.command_line.following = yes;
         self
     }
 
     /// Set the read_all option
     pub fn all(&mut self, yes: bool) -> &mut Self {
         self.read_all = yes;
+        self.files
+            .iter_mut()
+            .for_each(|buffer| buffer.read_all = yes);
         self
     }
 
-    /// The main application logic of the pager
+    /// The main application logic of the pager. Takes a reader for each of the input files
     pub fn run<T>(
         &mut self,
-        mut reader: T,
-        mut stdout: &mut std::io::Stdout,
+        mut readers: Vec<T>,
+        stdout: &mut std::io::Stdout,
     ) -> Result<(), Box<dyn std::error::Error>>
     where
-        T: std::io::BufRead,
+        T: std::io::BufRead + std::io::Seek,
     {
         // Perform setup
-        self.setup(&mut stdout)?;
+        self.setup(stdout)?;
 
         // The main program loop. Break when the exit flag is set.
         while !self.exit {
-            // Buffer lines as needed; based on the viewport
-            self.buffer_lines(&mut reader)?;
+            // Read from the file being shown
+            let Some(mut reader) = readers.get_mut(self.current) else {
+                break;
+            };
+
+            // Carry on skipping through the lines of the file, if we're in the middle of it
+            self.continue_skip(&mut reader)?;
+
+            // Buffer lines as needed; based on the viewport.
+            // When following the input, pick up everything new at the end of it instead.
+            if self.follow.is_on() {
+                self.follow_input(&mut reader)?;
+            } else {
+                self.buffer_lines(&mut reader)?;
+            }
+
+            // Carry on checking the lines against the filter
+            self.update_filter()?;
+
+            // Continue looking for the next search match, if we're in the middle of a scan
+            self.continue_scan(&mut reader)?;
+
+            // Carry on writing the lines to a file, if we're in the middle of it
+            self.continue_write(&mut reader);
+
+            // Keep the search match count in sync with the buffered lines
+            self.update_match_count(false)?;
 
             // Render the pager's view
-            self.render(&mut stdout)?;
+            self.render(stdout)?;
 
             // Handle key events before continuing to loop
             self.handle_events(&mut reader, stdout)?;
@@ -103,7 +266,7 @@ impl Pager {
         // Determine the layout sizes
         let sizes = layout::distribute(
             self.height,
-            &vec![layout::Size::Flexible, layout::Size::Fixed(1)],
+            &[layout::Size::Flexible, layout::Size::Fixed(1)],
         );
 
         // Setup subcomponents
@@ -118,22 +281,72 @@ impl Pager {
     // ----------------
 
     /// Buffer lines from the reader as needed
-    fn buffer_lines<T>(&mut self, reader: T) -> std::io::Result<()>
+    fn buffer_lines<T>(&mut self, reader: &mut T) -> std::io::Result<()>
     where
-        T: std::io::BufRead,
+        T: std::io::BufRead + std::io::Seek,
     {
-        for line in reader.lines() {
-            self.lines.push(line?);
-            // Read only up to the viewport's end + one more page unless the self.read_all flag is set
-            if !self.read_all {
-                if self.lines.len() > self.view.end() + self.view.height {
-                    break;
-                }
+        // The lines after those being skipped through are read once they've all been gone through
+        if self.skip.is_some() {
+            return Ok(());
+        }
+        // Read only up to the viewport's end + one more page unless the self.read_all flag is set
+        let chunk = self.view.height.max(1);
+        let deadline = Instant::now() + search::SCAN_TIME_SLICE;
+        // While the filter is still checking the lines, it has to catch up before there's any telling how many are needed
+        while !self.checking_filter()
+            && self.content().len() <= self.view.end(self.content()) + self.view.height
+        {
+            if self.read_lines(&mut *reader, chunk)? == 0 {
+                self.set_filter_reading(false);
+                return Ok(());
             }
+            // Few of the lines may pass the filter, so reading enough of them carries on on the next iteration of the event loop
+            if self.filter.is_some() && Instant::now() >= deadline {
+                self.set_filter_reading(true);
+                return Ok(());
+            }
+        }
+        self.set_filter_reading(false);
+        // Skip through the rest of the lines of a file rather than read them all, if they're all needed
+        if self.read_all && !self.start_skip(reader, usize::MAX, lines::Then::Stay)? {
+            while self.read_lines(&mut *reader, chunk)? > 0 {}
         }
         Ok(())
     }
 
+    /// Read up to `n` more lines from the reader. Returns the number of lines read
+    fn read_lines<T>(&mut self, mut reader: T, n: usize) -> std::io::Result<usize>
+    where
+        T: std::io::BufRead,
+    {
+        self.detect_binary(&mut reader)?;
+        let mut count = 0;
+        let mut at_end = false;
+        while count < n {
+            let mut raw = std::mem::take(&mut self.partial);
+            reader.read_until(b'\n', &mut raw)?;
+            if raw.is_empty() {
+                at_end = !self.follow.is_on();
+                break;
+            }
+            // While following, the rest of the line may still be on its way
+            if !raw.ends_with(b"\n") && self.follow.is_on() {
+                self.partial = raw;
+                break;
+            }
+            let line = lines::decode(&raw, self.control);
+            self.push_line(line, &raw);
+            count += 1;
+        }
+        // The last row of the hex dump is only complete once the end of the input is reached
+        let rows = self.update_hexdump(at_end);
+        if count > 0 || rows > 0 {
+            self.revision += 1;
+            self.update_filter()?;
+        }
+        Ok(count.max(rows))
+    }
+
     /// Set the exit flag to indicate that we need to exit the program
     fn exit(&mut self) {
         self.exit = true;
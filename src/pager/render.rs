@@ -7,8 +7,10 @@ impl Pager {
         stdout: &mut std::io::Stdout,
     ) -> Result<(), Box<dyn std::error::Error>> {
         // Render the view component
-        if self.view != self.prev.view {
-            self.prev.view = self.view.render(stdout, &self.lines)?; // Cache the frame until we need it again
+        if self.view != self.prev.view || self.revision != self.prev.revision {
+            self.load_view()?;
+            self.prev.view = self.view.render(stdout, self.content(), self.colours())?; // Cache the frame until we need it again
+            self.prev.revision = self.revision;
         }
         // Render the command line component
         if self.command_line != self.prev.command_line {
@@ -0,0 +1,594 @@
+use super::{
+    filter::Filter,
+    lines::CHUNK_LEN,
+    ui::{self, Mode},
+    Pager,
+};
+use crate::helpers;
+
+use std::time::{Duration, Instant};
+
+/// The number of lines to read from the reader at a time while looking for the next match
+const SEARCH_CHUNK_SIZE: usize = 1024;
+
+/// How long a scan may run before yielding back to the event loop
+pub(super) const SCAN_TIME_SLICE: Duration = Duration::from_millis(30);
+
+/// A forward search in progress. Scans through the buffered lines and keeps reading more
+/// from the reader, a slice of time at a time, until a match is found or the reader is exhausted.
+pub(super) struct Scan {
+    /// The next row to scan
+    row: usize,
+    /// The byte offset in the row to start scanning from
+    col: usize,
+    /// The number of rows scanned so far
+    scanned: usize,
+    /// Whether the scan was started by submitting the query (as opposed to previewing it)
+    submitted: bool,
+}
+
+/// Keeps a tally of the search matches in the buffered lines.
+/// The lines are counted a slice of time at a time, as they may have to be read back from the input.
+#[derive(Default)]
+pub(super) struct Tally {
+    /// The number of matches in each chunk of the lines counted so far
+    counts: Vec<usize>,
+    /// The number of lines counted so far
+    counted: usize,
+    /// The total number of matches counted so far
+    total: usize,
+    /// The selected match when the tally was last updated
+    selected: Option<helpers::Match>,
+}
+
+/// A snapshot of the view taken when the FIND prompt opens (or a search is submitted).
+/// Incremental searches start from here, and it is restored if the search is cancelled.
+#[derive(Clone)]
+pub(super) struct Origin {
+    scroll_row: usize,
+    scroll_col: usize,
+    search: Option<helpers::Pattern>,
+    selected: Option<helpers::Match>,
+}
+
+/// The state of the command-line before it handled an event
+pub(super) struct Prompt {
+    pub mode: Mode,
+    pub input: String,
+    pub case: helpers::Case,
+}
+
+impl Pager {
+    /// Search for the pattern in the command-line input
+    pub(super) fn search<T>(&mut self, reader: T) -> std::io::Result<()>
+    where
+        T: std::io::BufRead,
+    {
+        let input = self.command_line.input.clone();
+
+        // An empty query clears the search
+        if input.is_empty() {
+            self.clear_search();
+            self.origin = Some(self.snapshot());
+            return Ok(());
+        }
+
+        match helpers::Pattern::new(&input, self.command_line.case) {
+            Ok(pattern) => {
+                // Whether the incremental search has already jumped to the first match for this query
+                let previewed = self
+                    .origin
+                    .as_ref()
+                    .is_some_and(|o| o.search.as_ref() != Some(&pattern));
+
+                if self.view.search.as_ref() != Some(&pattern) {
+                    self.clear_search();
+                    self.view.search = Some(pattern);
+                    self.start_scan(reader, true)?;
+                } else if !previewed {
+                    // Submitting the same query again jumps to the next match
+                    self.start_scan(reader, true)?;
+                } else if let Some(scan) = &mut self.scan {
+                    // The preview is still looking for the first match
+                    scan.submitted = true;
+                } else {
+                    self.report_not_found(true)?;
+                }
+            }
+            Err(e) => self.command_line.message = format!("Invalid pattern: {e}"),
+        }
+
+        // The submitted search becomes the starting point for the next query
+        self.origin = Some(self.snapshot());
+        Ok(())
+    }
+
+    /// Highlight and jump to the first match as the query is typed into the FIND prompt.
+    /// Cancelling the prompt with Esc restores the view to where it was before the search.
+    pub(super) fn incremental_search<T>(&mut self, prev: Prompt, reader: T) -> std::io::Result<()>
+    where
+        T: std::io::BufRead,
+    {
+        let mode = self.command_line.mode.clone();
+        match (prev.mode, mode) {
+            // The FIND prompt was just opened
+            (prev, Mode::Search) if prev != Mode::Search => {
+                self.origin = Some(self.snapshot());
+            }
+            // The FIND prompt was cancelled
+            (Mode::Search, Mode::Base) => {
+                if let Some(origin) = self.origin.take() {
+                    self.restore(origin);
+                }
+            }
+            // The query (or its case-sensitivity) was modified
+            (Mode::Search, Mode::Search)
+                if prev.input != self.command_line.input || prev.case != self.command_line.case =>
+            {
+                self.preview(reader)?;
+            }
+            (Mode::Search, _) => self.origin = None,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Preview the query in the FIND prompt by highlighting and jumping to its first match after the origin
+    fn preview<T>(&mut self, reader: T) -> std::io::Result<()>
+    where
+        T: std::io::BufRead,
+    {
+        let Some(origin) = self.origin.clone() else {
+            return Ok(());
+        };
+
+        // Go back to where we started, with the search as it was
+        self.restore(origin);
+
+        // Patterns that are still being typed may not be valid yet, so they are simply not previewed
+        let input = &self.command_line.input;
+        if input.is_empty() {
+            return Ok(());
+        }
+        if let Ok(pattern) = helpers::Pattern::new(input, self.command_line.case) {
+            self.clear_search();
+            self.view.search = Some(pattern);
+            self.next_match(reader)?;
+        }
+        Ok(())
+    }
+
+    /// Take a snapshot of the view's scroll position and search state
+    fn snapshot(&self) -> Origin {
+        Origin {
+            scroll_row: self.view.scroll_row,
+            scroll_col: self.view.scroll_col,
+            search: self.view.search.clone(),
+            selected: self.view.selected.clone(),
+        }
+    }
+
+    /// Restore the view's scroll position and search state from the snapshot
+    fn restore(&mut self, origin: Origin) {
+        self.clear_search();
+        self.view.scroll_row = origin.scroll_row;
+        self.view.scroll_col = origin.scroll_col;
+        self.view.search = origin.search;
+        self.view.selected = origin.selected;
+    }
+
+    /// Jump to the next match after the current one (or after the top of the viewport).
+    /// Buffers more lines from the reader until a match is found or the reader is exhausted.
+    pub(super) fn next_match<T>(&mut self, reader: T) -> std::io::Result<()>
+    where
+        T: std::io::BufRead,
+    {
+        self.start_scan(reader, false)
+    }
+
+    /// Start scanning forward for the next match. If it isn't found straight away,
+    /// the scan continues in the background on each iteration of the event loop.
+    fn start_scan<T>(&mut self, reader: T, submitted: bool) -> std::io::Result<()>
+    where
+        T: std::io::BufRead,
+    {
+        if self.view.search.is_none() {
+            return Ok(());
+        }
+
+        // Start searching right after the selected match if it's still in view
+        let (row, col) = match self.selected_row() {
+            Some((row, m)) if self.view.is_visible(row, self.content()) => (row, m.end),
+            _ => (self.view.start(), 0),
+        };
+
+        self.scan = Some(Scan {
+            row,
+            col,
+            scanned: 0,
+            submitted,
+        });
+        self.continue_scan(reader)
+    }
+
+    /// Continue the scan in progress (if any) for a slice of time
+    pub(super) fn continue_scan<T>(&mut self, mut reader: T) -> std::io::Result<()>
+    where
+        T: std::io::BufRead,
+    {
+        // The scan carries on once the lines being skipped through have all been gone through
+        if self.skip.is_some() {
+            return Ok(());
+        }
+        let (Some(mut scan), Some(pattern)) = (self.scan.take(), self.view.search.clone()) else {
+            return Ok(());
+        };
+
+        let deadline = Instant::now() + SCAN_TIME_SLICE;
+        loop {
+            self.load_row(scan.row)?;
+            while let Some((index, line)) = self.content().get(scan.row) {
+                if let Some(range) = pattern.next(line, scan.col) {
+                    self.command_line.scanning = None;
+                    self.select(
+                        scan.row,
+                        helpers::Match {
+                            line: index,
+                            start: range.start,
+                            end: range.end,
+                        },
+                    );
+                    return Ok(());
+                }
+                scan.row += 1;
+                scan.col = 0;
+                scan.scanned += 1;
+                if Instant::now() >= deadline {
+                    break;
+                }
+            }
+
+            // Out of time. Pick up where we left off on the next iteration of the event loop
+            if Instant::now() >= deadline {
+                self.command_line.scanning = Some(scan.scanned);
+                self.scan = Some(scan);
+                return Ok(());
+            }
+
+            // The next rows were dropped from memory. Read them back and keep looking
+            if scan.row < self.content().len() {
+                continue;
+            }
+
+            // Ran out of buffered lines. Read some more and keep looking
+            if self.read_lines(&mut reader, SEARCH_CHUNK_SIZE)? == 0 {
+                self.command_line.scanning = None;
+                self.report_not_found(scan.submitted)?;
+                return Ok(());
+            }
+        }
+    }
+
+    /// Cancel the scan in progress, if any
+    pub(super) fn cancel_scan(&mut self) {
+        if self.scan.take().is_some() {
+            self.command_line.scanning = None;
+            self.command_line.message = String::from("Search cancelled");
+        }
+    }
+
+    /// Let the user know if the pattern doesn't match anything at all.
+    /// Searches that were submitted are also cleared so that the lines aren't highlighted for nothing.
+    fn report_not_found(&mut self, clear: bool) -> std::io::Result<()> {
+        self.update_match_count(true)?;
+        if self.tally.total == 0 {
+            if clear {
+                self.clear_search();
+            }
+            self.command_line.message = String::from("Pattern not found");
+        }
+        Ok(())
+    }
+
+    /// Jump to the previous match before the current one (or before the bottom of the viewport)
+    pub(super) fn prev_match(&mut self) -> std::io::Result<()> {
+        let Some(pattern) = self.view.search.clone() else {
+            return Ok(());
+        };
+        self.cancel_scan();
+
+        // Start searching right before the selected match if it's still in view
+        let (mut row, mut col) = match self.selected_row() {
+            Some((row, m)) if self.view.is_visible(row, self.content()) => (row, m.start),
+            _ => (
+                std::cmp::min(self.view.end(self.content()), self.content().len())
+                    .saturating_sub(1),
+                usize::MAX,
+            ),
+        };
+
+        loop {
+            self.load_row(row)?;
+            let Some((index, line)) = self.content().get(row) else {
+                return Ok(());
+            };
+            if let Some(range) = pattern.prev(line, col) {
+                self.select(
+                    row,
+                    helpers::Match {
+                        line: index,
+                        start: range.start,
+                        end: range.end,
+                    },
+                );
+                return Ok(());
+            }
+            if row == 0 {
+                return Ok(());
+            }
+            row -= 1;
+            col = usize::MAX;
+        }
+    }
+
+    /// Count the matches in any newly buffered lines and update the indicator in the command-line.
+    /// Counts for a slice of time, and carries on on the next iteration of the event loop, unless `all` is set.
+    pub(super) fn update_match_count(&mut self, all: bool) -> std::io::Result<()> {
+        let Some(pattern) = &self.view.search else {
+            return Ok(());
+        };
+
+        // Tally up the lines that haven't been counted yet, reading back those that were dropped from memory
+        let deadline = Instant::now() + SCAN_TIME_SLICE;
+        let tally = &mut self.tally;
+        while tally.counted < self.lines.len() && (all || Instant::now() < deadline) {
+            self.lines.trim(self.backing.lost());
+            self.lines
+                .load(tally.counted, &self.backing, self.control)?;
+            while let Some(line) = self.lines.get(tally.counted) {
+                let count = pattern.find_iter(line).count();
+                if tally.counted.is_multiple_of(CHUNK_LEN) {
+                    tally.counts.push(0);
+                }
+                if let Some(chunk) = tally.counts.last_mut() {
+                    *chunk += count;
+                }
+                tally.total += count;
+                tally.counted += 1;
+            }
+        }
+
+        // Determine the position of the selected match, if it moved (and its line has been counted)
+        let current = match &self.view.selected {
+            Some(m) if tally.selected.as_ref() == Some(m) => {
+                self.command_line.matches.and_then(|(c, _)| c)
+            }
+            Some(m) if m.line < tally.counted => {
+                self.lines.load(m.line, &self.backing, self.control)?;
+                let chunk = m.line / CHUNK_LEN;
+                let before: usize = tally.counts[..chunk].iter().sum::<usize>()
+                    + self
+                        .lines
+                        .leading(m.line)
+                        .iter()
+                        .map(|line| pattern.find_iter(line).count())
+                        .sum::<usize>();
+                let within = pattern
+                    .find_iter(self.lines.get(m.line).unwrap_or_default())
+                    .take_while(|r| r.start < m.start)
+                    .count();
+                tally.selected = self.view.selected.clone();
+                Some(before + within + 1)
+            }
+            Some(_) => None,
+            None => {
+                tally.selected = None;
+                None
+            }
+        };
+
+        self.command_line.matches = Some((current, tally.total));
+        Ok(())
+    }
+
+    /// Returns true if the matches of the search are still being counted
+    pub(super) fn counting(&self) -> bool {
+        self.view.search.is_some() && self.tally.counted < self.lines.len()
+    }
+
+    /// Clear the search pattern, the selected match and the tally
+    pub(super) fn clear_search(&mut self) {
+        self.scan = None;
+        self.command_line.scanning = None;
+        self.view.search = None;
+        self.view.selected = None;
+        self.tally = Tally::default();
+        self.command_line.matches = None;
+    }
+
+    /// The selected match and the row it's displayed at (if it isn't filtered out)
+    fn selected_row(&self) -> Option<(usize, helpers::Match)> {
+        let m = self.view.selected.clone()?;
+        let row = self.content().position(m.line).ok()?;
+        Some((row, m))
+    }
+
+    /// Select the given match and scroll its row into view
+    fn select(&mut self, row: usize, m: helpers::Match) {
+        let content = ui::Content {
+            lines: &self.lines,
+            filter: self.filter.as_ref().map(Filter::rows),
+        };
+        self.view.scroll_into_view(row, &m, content);
+        self.view.selected = Some(m);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pager() -> Pager {
+        let mut pager = Pager::init((80, 10));
+        pager.view.width = 80;
+        pager.view.height = 9;
+        pager
+    }
+
+    /// Submit the query in the FIND prompt
+    fn submit(pager: &mut Pager, query: &str, reader: impl std::io::BufRead) {
+        pager.command_line.mode = Mode::Search;
+        pager.command_line.input = query.to_string();
+        pager.search(reader).unwrap();
+        pager.update_match_count(true).unwrap();
+    }
+
+    #[test]
+    fn should_count_the_matches_up_to_the_selected_one() {
+        let mut pager = pager();
+        let mut reader = std::io::Cursor::new("x and x\nnothing\nx\n");
+        submit(&mut pager, "x", &mut reader);
+        assert_eq!(Some((Some(1), 3)), pager.command_line.matches);
+
+        pager.next_match(&mut reader).unwrap();
+        pager.update_match_count(true).unwrap();
+        assert_eq!(Some((Some(2), 3)), pager.command_line.matches);
+
+        pager.next_match(&mut reader).unwrap();
+        pager.update_match_count(true).unwrap();
+        assert_eq!(Some((Some(3), 3)), pager.command_line.matches);
+        assert_eq!(Some(2), pager.view.selected.as_ref().map(|m| m.line));
+    }
+
+    #[test]
+    fn should_report_a_pattern_that_is_not_found() {
+        let mut pager = pager();
+        let mut reader = std::io::Cursor::new("x and x\nnothing\nx\n");
+        submit(&mut pager, "zzz", &mut reader);
+        assert_eq!("Pattern not found", pager.command_line.message);
+        assert_eq!(None, pager.command_line.matches);
+        assert!(pager.view.search.is_none());
+    }
+
+    /// The state of the FIND prompt before the next key press
+    fn prompt(pager: &Pager) -> Prompt {
+        Prompt {
+            mode: pager.command_line.mode.clone(),
+            input: pager.command_line.input.clone(),
+            case: pager.command_line.case,
+        }
+    }
+
+    /// The lines with matches of `x` at lines 1, 5 and 12
+    fn lines() -> std::io::Cursor<String> {
+        let text: String = (0..20)
+            .map(|i| match i {
+                1 | 5 | 12 => format!("x {i}\n"),
+                _ => format!("line {i}\n"),
+            })
+            .collect();
+        std::io::Cursor::new(text)
+    }
+
+    #[test]
+    fn should_jump_to_the_first_match_after_the_origin_while_typing() {
+        let mut pager = pager();
+        let mut reader = lines();
+        pager.read_lines(&mut reader, 20).unwrap();
+        pager.view.scroll_row = 3;
+
+        let prev = prompt(&pager);
+        pager.command_line.mode = Mode::Search;
+        pager.incremental_search(prev, &mut reader).unwrap();
+
+        let prev = prompt(&pager);
+        pager.command_line.input = String::from("x");
+        pager.incremental_search(prev, &mut reader).unwrap();
+        assert_eq!(Some(5), pager.view.selected.as_ref().map(|m| m.line));
+
+        // Typing more of the query starts over from the origin, rather than from the match
+        let prev = prompt(&pager);
+        pager.command_line.input = String::from("x ");
+        pager.incremental_search(prev, &mut reader).unwrap();
+        assert_eq!(Some(5), pager.view.selected.as_ref().map(|m| m.line));
+    }
+
+    #[test]
+    fn should_go_back_to_the_origin_when_the_prompt_is_cancelled() {
+        let mut pager = pager();
+        let mut reader = lines();
+        pager.read_lines(&mut reader, 20).unwrap();
+        pager.view.scroll_row = 3;
+
+        let prev = prompt(&pager);
+        pager.command_line.mode = Mode::Search;
+        pager.incremental_search(prev, &mut reader).unwrap();
+        let prev = prompt(&pager);
+        pager.command_line.input = String::from("x 12");
+        pager.incremental_search(prev, &mut reader).unwrap();
+        assert_eq!(Some(12), pager.view.selected.as_ref().map(|m| m.line));
+        assert_ne!(3, pager.view.scroll_row);
+
+        let prev = prompt(&pager);
+        pager.command_line.mode = Mode::Base;
+        pager.incremental_search(prev, &mut reader).unwrap();
+        assert_eq!(3, pager.view.scroll_row);
+        assert!(pager.view.search.is_none());
+        assert!(pager.view.selected.is_none());
+    }
+
+    /// An input that takes longer than a slice of time to come in, a part at a time
+    struct Slow(Vec<String>);
+
+    impl std::io::Read for Slow {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.0.is_empty() {
+                return Ok(0);
+            }
+            std::thread::sleep(SCAN_TIME_SLICE);
+            let part = self.0.remove(0);
+            buf[..part.len()].copy_from_slice(part.as_bytes());
+            Ok(part.len())
+        }
+    }
+
+    /// More lines than are read at a time while scanning, and then a match
+    fn slow() -> std::io::BufReader<Slow> {
+        let lines = "line\n".repeat(SEARCH_CHUNK_SIZE + 100);
+        std::io::BufReader::with_capacity(lines.len(), Slow(vec![lines, String::from("x\n")]))
+    }
+
+    #[test]
+    fn should_carry_on_scanning_past_the_buffered_lines() {
+        let mut pager = pager();
+        let mut reader = slow();
+        submit(&mut pager, "x", &mut reader);
+        assert!(pager.scan.is_some());
+        assert!(pager.command_line.scanning.is_some());
+
+        let mut slices = 1;
+        while pager.scan.is_some() {
+            pager.continue_scan(&mut reader).unwrap();
+            slices += 1;
+        }
+        assert!(slices > 2);
+        assert_eq!(None, pager.command_line.scanning);
+        let selected = pager.view.selected.as_ref().map(|m| m.line);
+        assert_eq!(Some(SEARCH_CHUNK_SIZE + 100), selected);
+    }
+
+    #[test]
+    fn should_stop_scanning_when_cancelled() {
+        let mut pager = pager();
+        let mut reader = slow();
+        submit(&mut pager, "x", &mut reader);
+        assert!(pager.scan.is_some());
+
+        pager.cancel_scan();
+        assert!(pager.scan.is_none());
+        assert_eq!(None, pager.command_line.scanning);
+        assert_eq!("Search cancelled", pager.command_line.message);
+        pager.continue_scan(&mut reader).unwrap();
+        assert!(pager.view.selected.is_none());
+    }
+}
@@ -1,4 +1,5 @@
 use super::{CommandLine, Mode};
+use crate::helpers;
 
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 
@@ -6,23 +7,28 @@ impl CommandLine {
     /// Event handler for the command-line component. Returns true to stop to prevent event propagation
     pub fn handle_events(&mut self, event: &Event) -> Result<bool, Box<dyn std::error::Error>> {
         Ok(match self.mode {
-            Mode::Search | Mode::Goto => self.handle_search_and_goto_mode_events(event)?,
+            Mode::Search | Mode::Goto | Mode::Filter | Mode::Highlight | Mode::Command => {
+                self.handle_prompt_mode_events(event)?
+            }
             Mode::Base => self.handle_base_mode_events(event)?,
         })
     }
 
-    /// Handles events when in [Search][Mode::Search] or [Goto][Mode::Goto] mode
-    fn handle_search_and_goto_mode_events(
+    /// Handles events when in one of the prompt modes: [Search][Mode::Search], [Goto][Mode::Goto],
+    /// [Filter][Mode::Filter], [Highlight][Mode::Highlight] or [Command][Mode::Command]
+    fn handle_prompt_mode_events(
         &mut self,
         event: &Event,
     ) -> Result<bool, Box<dyn std::error::Error>> {
+        // The input may have been changed from outside since the last event
+        self.cursor = helpers::clamp_cursor(&self.input, self.cursor);
         match event {
             Event::Key(key_event) if key_event.kind == KeyEventKind::Press => match key_event {
                 KeyEvent {
                     code: KeyCode::Esc, ..
                 } => {
                     self.mode = Mode::Base;
-                    self.input.clear();
+                    self.clear_input();
                     return Ok(true);
                 }
                 KeyEvent {
@@ -31,7 +37,7 @@ impl CommandLine {
                     ..
                 } => {
                     self.mode = Mode::Search;
-                    self.input.clear();
+                    self.clear_input();
                     return Ok(true);
                 }
                 KeyEvent {
@@ -40,41 +46,199 @@ impl CommandLine {
                     ..
                 } => {
                     self.mode = Mode::Goto;
-                    self.input.clear();
+                    self.clear_input();
                     return Ok(true);
                 }
                 KeyEvent {
-                    code: KeyCode::Char(c),
+                    modifiers: KeyModifiers::ALT,
+                    code: KeyCode::Char('c'),
+                    ..
+                } if matches!(self.mode, Mode::Search | Mode::Filter | Mode::Highlight) => {
+                    self.case = self.case.next();
+                    return Ok(true);
+                }
+
+                // Recall previously submitted inputs
+                KeyEvent {
+                    code: code @ (KeyCode::Up | KeyCode::Down),
                     ..
                 } => {
-                    if self.mode == Mode::Goto {
-                        if c == &':' || c.is_numeric() {
-                            self.input.push(c.clone());
+                    let input = self.input.clone();
+                    if let Some(history) = self.history() {
+                        let recalled = match code {
+                            KeyCode::Up => history.prev(&input),
+                            _ => history.next(&input),
+                        };
+                        if let Some(recalled) = recalled {
+                            self.cursor = recalled.len();
+                            self.input = recalled;
                         }
                         return Ok(true);
                     }
-                    self.input.push(c.clone());
+                }
+
+                // Move the cursor
+                KeyEvent {
+                    modifiers: KeyModifiers::CONTROL,
+                    code: KeyCode::Left,
+                    ..
+                }
+                | KeyEvent {
+                    modifiers: KeyModifiers::ALT,
+                    code: KeyCode::Char('b'),
+                    ..
+                } => {
+                    self.cursor = helpers::prev_word(&self.input, self.cursor);
                     return Ok(true);
                 }
                 KeyEvent {
+                    modifiers: KeyModifiers::CONTROL,
+                    code: KeyCode::Right,
+                    ..
+                }
+                | KeyEvent {
+                    modifiers: KeyModifiers::ALT,
+                    code: KeyCode::Char('f'),
+                    ..
+                } => {
+                    self.cursor = helpers::next_word(&self.input, self.cursor);
+                    return Ok(true);
+                }
+                KeyEvent {
+                    code: KeyCode::Left,
+                    ..
+                } => {
+                    self.cursor = helpers::prev_char(&self.input, self.cursor);
+                    return Ok(true);
+                }
+                KeyEvent {
+                    code: KeyCode::Right,
+                    ..
+                } => {
+                    self.cursor = helpers::next_char(&self.input, self.cursor);
+                    return Ok(true);
+                }
+                KeyEvent {
+                    code: KeyCode::Home,
+                    ..
+                }
+                | KeyEvent {
+                    modifiers: KeyModifiers::CONTROL,
+                    code: KeyCode::Char('a'),
+                    ..
+                } => {
+                    self.cursor = 0;
+                    return Ok(true);
+                }
+                KeyEvent {
+                    code: KeyCode::End, ..
+                }
+                | KeyEvent {
+                    modifiers: KeyModifiers::CONTROL,
+                    code: KeyCode::Char('e'),
+                    ..
+                } => {
+                    self.cursor = self.input.len();
+                    return Ok(true);
+                }
+
+                // Delete text around the cursor
+                KeyEvent {
+                    modifiers: KeyModifiers::CONTROL,
+                    code: KeyCode::Char('u'),
+                    ..
+                } => {
+                    self.delete(0..self.cursor);
+                    return Ok(true);
+                }
+                KeyEvent {
+                    modifiers: KeyModifiers::CONTROL,
+                    code: KeyCode::Char('k'),
+                    ..
+                } => {
+                    self.delete(self.cursor..self.input.len());
+                    return Ok(true);
+                }
+                KeyEvent {
+                    modifiers: KeyModifiers::CONTROL,
+                    code: KeyCode::Char('w'),
+                    ..
+                }
+                | KeyEvent {
+                    modifiers: KeyModifiers::CONTROL | KeyModifiers::ALT,
                     code: KeyCode::Backspace,
                     ..
                 } => {
-                    if key_event.modifiers == KeyModifiers::CONTROL {
-                        let mut words: Vec<&str> = self.input.split(" ").collect();
-                        words.pop();
-                        self.input = words.join(" ");
-                    } else {
-                        self.input.pop();
-                    }
+                    self.delete(helpers::prev_word(&self.input, self.cursor)..self.cursor);
+                    return Ok(true);
+                }
+                KeyEvent {
+                    code: KeyCode::Backspace,
+                    ..
+                } => {
+                    self.delete(helpers::prev_char(&self.input, self.cursor)..self.cursor);
+                    return Ok(true);
+                }
+                KeyEvent {
+                    code: KeyCode::Delete,
+                    ..
+                } => {
+                    self.delete(self.cursor..helpers::next_char(&self.input, self.cursor));
+                    return Ok(true);
+                }
+
+                // Type text
+                KeyEvent {
+                    modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
+                    code: KeyCode::Char(c),
+                    ..
+                } => {
+                    self.insert(&c.to_string());
+                    return Ok(true);
                 }
                 _ => {}
             },
+            Event::Paste(text) => {
+                // The prompt is a single line, so join pasted lines with spaces
+                self.insert(&text.replace(['\r', '\n'], " "));
+                return Ok(true);
+            }
             _ => {}
         }
         Ok(false)
     }
 
+    /// Inserts the text at the cursor. In [Goto mode][Mode::Goto], only the characters that keep the input
+    /// a line number with a column, or a hex offset, are kept (see [helpers::is_goto_input])
+    fn insert(&mut self, text: &str) {
+        let text: String = match self.mode {
+            Mode::Goto => text.chars().fold(String::new(), |mut kept, c| {
+                let mut input = self.input.clone();
+                input.insert_str(self.cursor, &kept);
+                input.insert(self.cursor + kept.len(), c);
+                if helpers::is_goto_input(&input) {
+                    kept.push(c);
+                }
+                kept
+            }),
+            _ => text.to_string(),
+        };
+        self.input.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+    }
+
+    /// Deletes the range of the input, moving the cursor to where it was
+    fn delete(&mut self, range: std::ops::Range<usize>) {
+        self.cursor = range.start;
+        self.input.replace_range(range, "");
+    }
+
+    /// Clears the input and moves the cursor back to the start
+    fn clear_input(&mut self) {
+        self.input.clear();
+        self.cursor = 0;
+    }
+
     /// Handles events when in [Base mode][Mode::Base]
     fn handle_base_mode_events(
         &mut self,
@@ -104,6 +268,24 @@ impl CommandLine {
                     ..
                 } => self.mode = Mode::Goto,
 
+                // Switch to Filter Mode
+                KeyEvent {
+                    code: KeyCode::Char('&'),
+                    ..
+                } => self.mode = Mode::Filter,
+
+                // Switch to Highlight Mode
+                KeyEvent {
+                    code: KeyCode::Char('*'),
+                    ..
+                } => self.mode = Mode::Highlight,
+
+                // Switch to Command Mode
+                KeyEvent {
+                    code: KeyCode::Char('-'),
+                    ..
+                } => self.mode = Mode::Command,
+
                 // Catch all
                 _ => {}
             },
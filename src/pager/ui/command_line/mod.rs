@@ -1,3 +1,5 @@
+use crate::helpers;
+
 mod events;
 mod render;
 
@@ -8,9 +10,50 @@ pub struct CommandLine {
     /// Stores the user input
     pub input: String,
 
+    /// The position of the cursor in the input, as a byte offset
+    pub cursor: usize,
+
+    /// A status message to display to the user (e.g. an invalid search pattern)
+    pub message: String,
+
+    /// The position of the selected search match (if any) and the total number of matches
+    pub matches: Option<(Option<usize>, usize)>,
+
+    /// The patterns pinned to be highlighted in their own colours
+    pub highlights: Vec<helpers::Highlight>,
+
+    /// The number of lines scanned so far by the search in progress, if any
+    pub scanning: Option<usize>,
+
+    /// How far through the lines being skipped it has got, as a percentage, if it's in progress
+    pub skipping: Option<usize>,
+
+    /// The number of lines checked against the filter so far, if it's still checking them or reading more
+    pub filtering: Option<usize>,
+
+    /// The number of lines written so far to a file, if it's in progress
+    pub writing: Option<usize>,
+
+    /// Set while following the input for new contents
+    pub following: bool,
+
+    /// The name of the file being shown, its number and the number of files, when there are several
+    pub file: Option<(String, usize, usize)>,
+
+    /// The filter applied to the view, if any
+    pub filter: Option<String>,
+
     /// The current [mode][Mode] of the command-line
     pub mode: Mode,
 
+    /// How search queries treat upper and lower case letters
+    pub case: helpers::Case,
+
+    /// The previously submitted search queries
+    pub search_history: helpers::History,
+    /// The previously submitted goto commands
+    pub goto_history: helpers::History,
+
     /// The x-position (column number)
     pub x: u16,
     /// The y-position (row number)
@@ -28,6 +71,9 @@ pub enum Mode {
     Base,
     Goto,
     Search,
+    Filter,
+    Highlight,
+    Command,
 }
 
 impl CommandLine {
@@ -39,4 +85,13 @@ impl CommandLine {
         self.height = size.1;
         Ok(())
     }
+
+    /// The history of the current mode's prompt, if it keeps one
+    fn history(&mut self) -> Option<&mut helpers::History> {
+        match self.mode {
+            Mode::Search => Some(&mut self.search_history),
+            Mode::Goto => Some(&mut self.goto_history),
+            _ => None,
+        }
+    }
 }
@@ -11,54 +11,158 @@ use super::{CommandLine, Mode};
 use crate::helpers;
 
 impl CommandLine {
-    /// The render function is responsible for rendering the component out to the screen
+    /// The render function is responsible for rendering the component out to the screen.
+    /// The mode, the status and the input are laid out from the left, and the help gets whatever room is left on the right.
     pub fn render(&self, stdout: &mut std::io::Stdout) -> std::io::Result<Self> {
+        let mut line = String::new();
+        self.render_mode(&mut line);
+        self.render_input(&mut line);
+        self.render_message(&mut line);
+        // Cut the line short on narrow terminals, rather than letting it spill onto another line
+        let width = self.width.saturating_sub(1);
+        let line = helpers::truncate_visible(&line, width);
+        let help = self.render_help(width.saturating_sub(helpers::visible_width(&line) + 2));
         stdout
             .queue(cursor::MoveTo(self.x, self.y))?
-            .queue(Clear(ClearType::CurrentLine))?;
-        self.render_help(stdout)?;
-        self.render_mode(stdout)?;
-        self.render_input(stdout)?;
+            .queue(Clear(ClearType::CurrentLine))?
+            .queue(Print(line))?;
+        if !help.is_empty() {
+            stdout
+                .queue(cursor::MoveToColumn(
+                    width.saturating_sub(helpers::visible_width(&help)) as u16,
+                ))?
+                .queue(Print(help))?
+                .queue(cursor::MoveToColumn(self.x))?;
+        }
         stdout.flush()?;
         Ok(self.clone()) // Return a clone of this frame so that we can cache it and determine if we need to re-render
     }
 
     /// Shows the current mode of the operations of the command-line
-    fn render_mode(&self, stdout: &mut std::io::Stdout) -> std::io::Result<()> {
+    fn render_mode(&self, line: &mut String) {
         let mode = match self.mode {
             Mode::Base => style(""),
             Mode::Goto => style(" GOTO ").black().on_cyan(),
             Mode::Search => style(" FIND ").black().on_dark_yellow(),
+            Mode::Filter => style(" FILTER ").black().on_dark_magenta(),
+            Mode::Highlight => style(" HIGHLIGHT ").black().on_dark_green(),
+            Mode::Command => style(" COMMAND ").black().on_dark_blue(),
         };
-        stdout.queue(Print(" "))?.queue(Print(mode))?;
-        Ok(())
+        line.push_str(&format!(" {mode}"));
+
+        // Show how the search query treats upper and lower case letters
+        if matches!(self.mode, Mode::Search | Mode::Filter | Mode::Highlight) {
+            let case = match self.case {
+                helpers::Case::Smart => "smart-case",
+                helpers::Case::Insensitive => "ignore-case",
+                helpers::Case::Sensitive => "match-case",
+            };
+            line.push_str(&format!(" {}", style(case).dark_grey()));
+        }
+
+        // List the pinned highlights in their colours
+        if self.mode == Mode::Highlight {
+            for highlight in &self.highlights {
+                let pattern = style(format!(" {} ", highlight.pattern.as_str()));
+                line.push_str(&format!(" {}", pattern.black().on(highlight.color)));
+            }
+        }
+
+        // Show which of the files is being shown
+        if let Some((name, n, total)) = &self.file {
+            line.push_str(&format!(
+                " {}",
+                style(format!("{name} ({n}/{total})")).dark_cyan()
+            ));
+        }
+
+        // Show that the input is being followed for new contents
+        if self.following {
+            line.push_str(&format!(" {}", style("Following...").dark_green().italic()));
+        }
+
+        // Show the filter applied to the view
+        if let Some(filter) = &self.filter {
+            line.push_str(&format!(" {}", style(format!("&{filter}")).dark_magenta()));
+        }
+
+        // Show the progress of the search in progress
+        if let Some(scanned) = self.scanning {
+            let progress = format!("Searching... {scanned} lines (Esc to cancel)");
+            line.push_str(&format!(" {}", style(progress).dark_grey().italic()));
+        }
+
+        // Show the progress of the skipping through the lines
+        if let Some(done) = self.skipping {
+            let progress = format!("Counting lines... {done}% (Esc to cancel)");
+            line.push_str(&format!(" {}", style(progress).dark_grey().italic()));
+        }
+
+        // Show the progress of the filter
+        if let Some(checked) = self.filtering {
+            let progress = format!("Filtering... {checked} lines (Esc to cancel)");
+            line.push_str(&format!(" {}", style(progress).dark_grey().italic()));
+        }
+
+        // Show the progress of the writing to a file
+        if let Some(written) = self.writing {
+            let progress = format!("Writing... {written} lines (Esc to cancel)");
+            line.push_str(&format!(" {}", style(progress).dark_grey().italic()));
+        }
+
+        // Show the search match count and the position of the selected match
+        if let Some((current, total)) = self.matches {
+            let count = match current {
+                Some(current) => format!("{current}/{total} matches"),
+                None => format!("{total} matches"),
+            };
+            line.push_str(&format!(" {}", style(count).dark_yellow()));
+        }
     }
 
     /// Renders the user-input on the command-line for visual feedback
-    fn render_input(&self, stdout: &mut std::io::Stdout) -> std::io::Result<()> {
-        stdout.queue(Print(" "))?; // Apply some padding
+    fn render_input(&self, line: &mut String) {
+        line.push(' '); // Apply some padding
 
-        let cursor = style("|").rapid_blink();
-
-        if self.input.len() > 0 {
-            stdout.queue(Print(&self.input))?.queue(Print(cursor))?;
+        if !self.input.is_empty() {
+            // Show the cursor over the character it is on, or after the input when at the end
+            let cursor = helpers::clamp_cursor(&self.input, self.cursor);
+            let (before, rest) = self.input.split_at(cursor);
+            let under = helpers::next_char(rest, 0);
+            let (under, after) = match under {
+                0 => (" ", ""),
+                _ => rest.split_at(under),
+            };
+            line.push_str(&format!("{before}{}{after}", style(under).reverse()));
         } else {
+            let cursor = style(" ").reverse();
             let placeholder = style(match self.mode {
                 Mode::Search => "Enter Search Query...",
-                Mode::Goto => "Enter Line or Line:Column",
+                Mode::Goto => "Enter Line, Line:Column or 0xOffset",
+                Mode::Filter => "Enter Filter Pattern (prefix with ! to invert)...",
+                Mode::Highlight => "Enter Pattern to Pin (prefix with - to remove)...",
+                Mode::Command => {
+                    "Enter Command (set number, set wrap, set hex, set tabstop=N, write FILE, filter PATTERN, next, prev)..."
+                }
                 Mode::Base => "",
             })
             .dark_grey()
             .italic();
             if self.mode != Mode::Base {
-                stdout.queue(Print(cursor))?.queue(Print(placeholder))?;
+                line.push_str(&format!("{cursor}{placeholder}"));
             }
         }
-        Ok(())
     }
 
-    /// Renders the contextual help message
-    fn render_help(&self, stdout: &mut std::io::Stdout) -> std::io::Result<()> {
+    /// Renders the status message, if there is one
+    fn render_message(&self, line: &mut String) {
+        if !self.message.is_empty() {
+            line.push_str(&format!("  {}", style(&self.message).red()));
+        }
+    }
+
+    /// Renders the contextual help message, keeping as many of its entries as fit in the given width
+    fn render_help(&self, width: usize) -> String {
         let enter = style("Enter").dark_green();
         let esc = style("Esc").dark_green();
         let slash = style("/").dark_green();
@@ -66,33 +170,71 @@ impl CommandLine {
         let comma = style(", ").dark_grey().italic();
         let ctrl_f = style("Ctrl+F").dark_green();
         let ctrl_g = style("Ctrl+G").dark_green();
+        let alt_c = style("Alt+C").dark_green();
+        let up_down = style("↑↓").dark_green();
+        let history = style("History").dark_grey().italic();
+        let case = style("Case").dark_grey().italic();
         let find = style("Find").dark_grey().italic();
         let goto = style("Goto").dark_grey().italic();
+        let ampersand = style("&").dark_green();
+        let filter = style("Filter").dark_grey().italic();
+        let star = style("*").dark_green();
+        let highlight = style("Highlight").dark_grey().italic();
+        let minus = style("-").dark_green();
+        let command = style("Command").dark_grey().italic();
+        let n = style("n").dark_green();
+        let shift_f = style("F").dark_green();
+        let follow = style("Follow").dark_grey().italic();
+        let x = style("x").dark_green();
+        let hex = style("Hex").dark_grey().italic();
+        let shift_n = style("N").dark_green();
+        let next_prev = style("Next/Prev").dark_grey().italic();
+        let close_bracket = style("]").dark_green();
+        let open_bracket = style("[").dark_green();
+        let next_prev_file = style("Next/Prev File").dark_grey().italic();
         let submit = style("Submit").dark_grey().italic();
         let back = style("Back").dark_grey().italic();
         let quit = style("Quit").dark_grey().italic();
         let dot = style("•").dark_grey();
         let help_message = match self.mode {
             Mode::Search => {
-                format!("{enter} {submit} {dot} {ctrl_g} {goto} {dot} {esc} {back}")
+                format!("{enter} {submit} {dot} {up_down} {history} {dot} {alt_c} {case} {dot} {ctrl_g} {goto} {dot} {esc} {back}")
             }
             Mode::Goto => {
-                format!("{enter} {submit} {dot} {ctrl_f} {find} {dot} {esc} {back}")
+                format!("{enter} {submit} {dot} {up_down} {history} {dot} {ctrl_f} {find} {dot} {esc} {back}")
+            }
+            Mode::Command => {
+                format!("{enter} {submit} {dot} {esc} {back}")
+            }
+            Mode::Filter | Mode::Highlight => {
+                format!("{enter} {submit} {dot} {alt_c} {case} {dot} {esc} {back}")
             }
             Mode::Base => {
+                // Going to the next or previous file only applies when there are several
+                let files = match self.file {
+                    Some(_) => {
+                        format!("{close_bracket}{comma}{open_bracket} {next_prev_file} {dot} ")
+                    }
+                    None => String::new(),
+                };
                 format!(
-                        "{ctrl_f}{comma}{slash} {find} {dot} {ctrl_g}{comma}{colon} {goto} {dot} {esc} {quit}"
+                        "{ctrl_f}{comma}{slash} {find} {dot} {n}{comma}{shift_n} {next_prev} {dot} {files}{shift_f} {follow} {dot} {x} {hex} {dot} {ampersand} {filter} {dot} {star} {highlight} {dot} {minus} {command} {dot} {ctrl_g}{comma}{colon} {goto} {dot} {esc} {quit}"
                     )
             }
         };
-        stdout
-            .queue(cursor::MoveToColumn(
-                self.width
-                    .saturating_sub(helpers::visible_width(&help_message.to_string()) + 1)
-                    as u16,
-            ))?
-            .queue(Print(help_message))?
-            .queue(cursor::MoveToColumn(self.x))?;
-        Ok(())
+        // Leave out the entries at the end that don't fit, rather than cutting one short
+        let separator = format!(" {dot} ");
+        let mut help = String::new();
+        for entry in help_message.split(&separator) {
+            let joined = match help.is_empty() {
+                true => entry.to_string(),
+                false => format!("{help}{separator}{entry}"),
+            };
+            if helpers::visible_width(&joined) > width {
+                break;
+            }
+            help = joined;
+        }
+        help
     }
 }
@@ -0,0 +1,164 @@
+use crate::pager::lines::Lines;
+
+/// The contents displayed in the view.
+/// These are the buffered lines, or only the lines that pass the filter if one has been applied.
+/// The view scrolls through the rows of the content, while each row remembers its original line index.
+/// Only the lines kept in memory can be displayed, so the rows in view have to be loaded beforehand.
+#[derive(Clone, Copy)]
+pub struct Content<'a> {
+    /// The collection of buffered lines
+    pub lines: &'a Lines,
+    /// The indices of the lines that pass the filter, if one has been applied
+    pub filter: Option<&'a Rows>,
+}
+
+/// The indices of the lines that pass a filter, in ascending order.
+/// They're kept as runs of consecutive lines, as a filter tends to let through (or leave out) whole stretches of them.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Rows {
+    /// The index of the first line of each run, and the row it's displayed at
+    runs: Vec<(usize, usize)>,
+    /// The number of rows
+    len: usize,
+}
+
+impl Rows {
+    /// The number of rows
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Add the line at the given index, which comes after all the others, as the last row
+    pub fn push(&mut self, index: usize) {
+        match self.runs.last() {
+            Some(&(first, row)) if first + (self.len - row) == index => {}
+            _ => self.runs.push((index, self.len)),
+        }
+        self.len += 1;
+    }
+
+    /// Returns the index of the line at the given row
+    pub fn get(&self, row: usize) -> Option<usize> {
+        if row >= self.len {
+            return None;
+        }
+        let (first, start) = self.runs[self.runs.partition_point(|&(_, r)| r <= row) - 1];
+        Some(first + (row - start))
+    }
+
+    /// Find the row of the line at the given index.
+    /// If it isn't one of the rows, returns the row at which it would have been as the error.
+    pub fn position(&self, index: usize) -> Result<usize, usize> {
+        let run = self.runs.partition_point(|&(first, _)| first <= index);
+        let Some(&(first, start)) = run.checked_sub(1).map(|run| &self.runs[run]) else {
+            return Err(0);
+        };
+        let end = self.runs.get(run).map_or(self.len, |&(_, row)| row);
+        match start + (index - first) {
+            row if row < end => Ok(row),
+            _ => Err(end),
+        }
+    }
+}
+
+impl FromIterator<usize> for Rows {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut rows = Rows::default();
+        iter.into_iter().for_each(|index| rows.push(index));
+        rows
+    }
+}
+
+impl<'a> Content<'a> {
+    /// The number of rows in the content
+    pub fn len(&self) -> usize {
+        match self.filter {
+            Some(rows) => rows.len(),
+            None => self.lines.len(),
+        }
+    }
+
+    /// Returns the original line index at the given row
+    pub fn index(&self, row: usize) -> Option<usize> {
+        match self.filter {
+            Some(rows) => rows.get(row),
+            None => (row < self.lines.len()).then_some(row),
+        }
+    }
+
+    /// Returns the original line index and the line at the given row, if the line is in memory
+    pub fn get(&self, row: usize) -> Option<(usize, &'a str)> {
+        let index = self.index(row)?;
+        self.lines.get(index).map(|line| (index, line))
+    }
+
+    /// Find the row that displays the line at the given index.
+    /// If the line is filtered out, returns the row at which it would have been displayed as the error.
+    pub fn position(&self, index: usize) -> Result<usize, usize> {
+        match self.filter {
+            Some(rows) => rows.position(index),
+            None if index < self.lines.len() => Ok(index),
+            None => Err(self.lines.len()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines() -> Lines {
+        let mut lines = Lines::default();
+        for i in 0..5 {
+            lines.push(format!("line {i}"), 7);
+        }
+        lines
+    }
+
+    #[test]
+    fn should_map_rows_to_lines_one_to_one_without_a_filter() {
+        let lines = lines();
+        let content = Content {
+            lines: &lines,
+            filter: None,
+        };
+        assert_eq!(5, content.len());
+        assert_eq!(Some((2, "line 2")), content.get(2));
+        assert_eq!(Ok(4), content.position(4));
+        assert_eq!(Err(5), content.position(7));
+    }
+
+    #[test]
+    fn should_map_rows_to_the_filtered_lines() {
+        let lines = lines();
+        let rows = Rows::from_iter([1, 3]);
+        let content = Content {
+            lines: &lines,
+            filter: Some(&rows),
+        };
+        assert_eq!(2, content.len());
+        assert_eq!(Some((3, "line 3")), content.get(1));
+        assert_eq!(None, content.get(2));
+        assert_eq!(Ok(1), content.position(3));
+        assert_eq!(Err(1), content.position(2));
+    }
+
+    #[test]
+    fn should_keep_the_filtered_lines_as_runs() {
+        let rows = Rows::from_iter([2, 3, 4, 8, 10, 11]);
+        assert_eq!(3, rows.runs.len());
+        assert_eq!(6, rows.len());
+        let lines: Vec<_> = (0..7).map(|row| rows.get(row)).collect();
+        assert_eq!(
+            vec![Some(2), Some(3), Some(4), Some(8), Some(10), Some(11), None],
+            lines
+        );
+        assert_eq!(Err(0), rows.position(1));
+        assert_eq!(Ok(2), rows.position(4));
+        assert_eq!(Err(3), rows.position(5));
+        assert_eq!(Ok(3), rows.position(8));
+        assert_eq!(Err(4), rows.position(9));
+        assert_eq!(Ok(5), rows.position(11));
+        assert_eq!(Err(6), rows.position(12));
+    }
+}
@@ -1,4 +1,4 @@
-use super::View;
+use super::{Content, View};
 
 use crossterm::event::{Event, KeyCode, KeyEventKind, MouseEventKind};
 
@@ -7,24 +7,25 @@ impl View {
     pub fn handle_events(
         &mut self,
         event: &Event,
-        lines: &Vec<String>,
+        content: Content,
     ) -> Result<bool, Box<dyn std::error::Error>> {
         Ok(match event {
             Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
                 match key_event.code {
                     KeyCode::Up | KeyCode::Char('k') => self.scroll_up(1),
-                    KeyCode::Down | KeyCode::Char('j') => self.scroll_down(1, lines),
+                    KeyCode::Down | KeyCode::Char('j') => self.scroll_down(1, content),
                     KeyCode::Left | KeyCode::Char('h') => self.scroll_left(1),
                     KeyCode::Right | KeyCode::Char('l') => self.scroll_right(1),
-                    KeyCode::PageUp => self.page_up(),
-                    KeyCode::PageDown => self.page_down(lines),
+                    KeyCode::PageUp => self.page_up(content),
+                    KeyCode::PageDown => self.page_down(content),
                     KeyCode::Home => self.home(),
+                    KeyCode::Char('w') => self.toggle_wrap(),
                     _ => false,
                 }
             }
             Event::Mouse(mouse_event) => match mouse_event.kind {
                 MouseEventKind::ScrollUp => self.scroll_up(1),
-                MouseEventKind::ScrollDown => self.scroll_down(1, lines),
+                MouseEventKind::ScrollDown => self.scroll_down(1, content),
                 _ => false,
             },
             _ => false,
@@ -36,47 +37,60 @@ impl View {
         if self.start() > 0 {
             self.scroll_row = self.scroll_row.saturating_sub(n);
         }
-        return false;
+        false
     }
 
     /// Scroll down by the given number of lines
-    fn scroll_down(&mut self, n: usize, lines: &Vec<String>) -> bool {
-        if self.end() < lines.len() {
+    fn scroll_down(&mut self, n: usize, content: Content) -> bool {
+        if self.end(content) < content.len() {
             self.scroll_row = self.scroll_row.saturating_add(n);
         }
-        return false;
+        false
     }
 
     /// Scroll left horizontally by the given number of columns
     fn scroll_left(&mut self, n: usize) -> bool {
         self.scroll_col = self.scroll_col.saturating_sub(n);
-        return false;
+        false
     }
 
     /// Scroll right horizontally by the given number of columns
     fn scroll_right(&mut self, n: usize) -> bool {
         self.scroll_col = self.scroll_col.saturating_add(n);
-        return false;
+        false
     }
 
     /// Scroll up by one page
-    fn page_up(&mut self) -> bool {
-        if self.start() > self.height {
+    fn page_up(&mut self, content: Content) -> bool {
+        if self.wrap {
+            self.scroll_row = self.rows_before(content, self.scroll_row, self.text_height());
+        } else if self.start() > self.height {
             self.scroll_row = self.scroll_row.saturating_sub(self.height - 1)
         } else {
             self.scroll_row = 0;
         }
-        return false;
+        false
     }
 
     // Scroll down by one page
-    fn page_down(&mut self, lines: &Vec<String>) -> bool {
-        if self.end() + self.height < lines.len() {
+    fn page_down(&mut self, content: Content) -> bool {
+        let end = self.end(content);
+        if self.wrap {
+            if end < content.len() {
+                self.scroll_row = end.max(self.scroll_row + 1);
+            }
+        } else if end + self.height < content.len() {
             self.scroll_row = self.scroll_row.saturating_add(self.height - 1)
-        } else if self.end() < lines.len() {
-            self.scroll_row = lines.len() - self.height + 1;
+        } else if end < content.len() {
+            self.scroll_row = content.len() - self.height + 1;
         }
-        return false;
+        false
+    }
+
+    /// Toggle wrapping long lines
+    fn toggle_wrap(&mut self) -> bool {
+        self.wrap = !self.wrap;
+        false
     }
 
     /// Scroll to the home position.
@@ -89,7 +103,7 @@ impl View {
         } else {
             self.scroll_row = 0;
         }
-        return false;
+        false
     }
 
     // NOTE: Scrolling to the end is handled at the Pager level because the view component doesn't
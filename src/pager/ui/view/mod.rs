@@ -1,15 +1,22 @@
 use crate::helpers;
 
+mod content;
 mod events;
 mod render;
 
+pub use content::{Content, Rows};
+
 /// Represents a viewport
 #[derive(Default, Clone, PartialEq, Eq)]
 pub struct View {
-    /// The string to search for in the view
-    pub search: String,
+    /// The pattern to search for in the view
+    pub search: Option<helpers::Pattern>,
+    /// The currently selected search match
+    pub selected: Option<helpers::Match>,
+    /// The patterns pinned to be highlighted in their own colours
+    pub highlights: Vec<helpers::Highlight>,
 
-    /// The index of the first-line to display in the viewport
+    /// The index of the first row to display in the viewport
     pub scroll_row: usize,
     /// The index of the first-column to display in the viewport
     pub scroll_col: usize,
@@ -18,6 +25,14 @@ pub struct View {
     pub show_line_numbers: bool,
     /// Should show borders
     pub show_borders: bool,
+    /// Should wrap long lines onto the following rows of the viewport
+    pub wrap: bool,
+    /// The number of columns between tab stops
+    pub tabstop: usize,
+    /// Whether the rows are a hex dump of the input, which shows the offsets instead of line numbers
+    pub hexdump: bool,
+    /// Whether the rows are the last lines of a file whose numbers aren't known yet, as the lines before them are still being counted
+    pub tail: bool,
 
     /// The x-position (column number)
     pub x: u16,
@@ -33,19 +48,136 @@ pub struct View {
 }
 
 impl View {
-    /// The start of the viewport. Index of the first visible line
+    /// The start of the viewport. Index of the first visible row
     pub fn start(&self) -> usize {
         self.scroll_row
     }
 
-    /// The end of the viewport. Index of the last visible line
-    pub fn end(&self) -> usize {
+    /// The end of the viewport. Index of the row after the last fully visible row
+    pub fn end(&self, content: Content) -> usize {
+        let height = self.text_height();
+        if !self.wrap {
+            return self.scroll_row + height;
+        }
+        let mut used = 0;
+        let mut row = self.scroll_row;
+        while row < content.len() {
+            used += self.row_height(content, row);
+            if used > height {
+                return row;
+            }
+            row += 1;
+        }
+        // Past the end of the contents, each row takes up a single line
+        row + (height - used)
+    }
+
+    /// Returns true if the row at the given index is within the viewport
+    pub fn is_visible(&self, row: usize, content: Content) -> bool {
+        row >= self.start() && row < self.end(content)
+    }
+
+    /// The number of lines of the viewport available to the contents
+    fn text_height(&self) -> usize {
         let borders = if self.show_borders {
             self.borders.height_reduction()
         } else {
             0
         };
-        self.scroll_row + self.height - borders
+        self.height.saturating_sub(borders)
+    }
+
+    /// The number of columns the text takes up on screen, once its tabs are expanded
+    pub fn display_width(&self, text: &str) -> usize {
+        helpers::visible_width(&helpers::expand_tabs(text, self.tabstop))
+    }
+
+    /// The screen column at which the character at the given position of the line is displayed
+    pub fn display_column(&self, line: &str, chars: usize) -> usize {
+        let end = line
+            .char_indices()
+            .nth(chars)
+            .map_or(line.len(), |(i, _)| i);
+        self.display_width(&line[..end])
+    }
+
+    /// The number of lines of the viewport the row takes up. Only long lines that wrap take up more than one.
+    pub fn row_height(&self, content: Content, row: usize) -> usize {
+        match content.get(row) {
+            Some((index, line)) if self.wrap => self
+                .display_width(line)
+                .div_ceil(self.text_width(index).max(1))
+                .max(1),
+            _ => 1,
+        }
+    }
+
+    /// The first of the rows before the given one that, together, fit in the given number of lines.
+    /// Always goes back at least one row, even if it doesn't fit.
+    fn rows_before(&self, content: Content, row: usize, lines: usize) -> usize {
+        let mut first = row;
+        let mut used = 0;
+        while first > 0 {
+            used += self.row_height(content, first - 1);
+            if used > lines && first < row {
+                break;
+            }
+            first -= 1;
+        }
+        first
+    }
+
+    /// Scroll down so that the last row is at the bottom of the viewport, with a blank line after it
+    pub fn scroll_to_end(&mut self, content: Content) {
+        self.scroll_row = if self.wrap {
+            self.rows_before(content, content.len(), self.text_height().saturating_sub(1))
+        } else {
+            (content.len() + 1).saturating_sub(self.height)
+        };
+    }
+
+    /// Whether the rows are numbered. The rows of a hex dump start with their offsets instead
+    fn numbered(&self) -> bool {
+        self.show_line_numbers && !self.hexdump
+    }
+
+    /// The number shown next to the line at the given index. A question mark if it isn't known yet
+    fn line_number(&self, index: usize) -> String {
+        match self.tail {
+            true => format!("{:>3}", "?"),
+            false => format!("{:>3}", index + 1),
+        }
+    }
+
+    /// The number of columns available to the contents of the line at the given index
+    pub fn text_width(&self, index: usize) -> usize {
+        let gutter = if self.numbered() {
+            self.line_number(index).len() + 3
+        } else {
+            0
+        };
+        self.width
+            .saturating_sub(self.borders.width_reduction() + 2 + gutter)
+    }
+
+    /// Scroll the viewport (vertically and horizontally) so that the given match, displayed at the given row, is visible
+    pub fn scroll_into_view(&mut self, row: usize, m: &helpers::Match, content: Content) {
+        if !self.is_visible(row, content) {
+            self.scroll_row = row;
+        }
+        if self.wrap {
+            return; // Wrapped lines are shown in full
+        }
+        // The match is given in bytes, while the view scrolls by terminal columns
+        let Some((_, line)) = content.get(row) else {
+            return;
+        };
+        let start = self.display_width(line.get(..m.start).unwrap_or(line));
+        let end = self.display_width(line.get(..m.end).unwrap_or(line));
+        let width = self.text_width(m.line);
+        if start < self.scroll_col || end > self.scroll_col + width {
+            self.scroll_col = if end <= width { 0 } else { start };
+        }
     }
 
     /// Perform setup. The setup function is called once on initialization
@@ -6,97 +6,215 @@ use crossterm::{
     QueueableCommand,
 };
 
-use super::View;
+use super::{Content, View};
 use crate::helpers;
 
+/// The layers a line can be painted with
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Layer {
+    /// The pinned highlight at the given index
+    Highlight(usize),
+    /// A search match
+    Match,
+    /// The selected search match
+    Selected,
+}
+
 impl View {
     /// Render the view component
     pub fn render(
         &self,
         stdout: &mut std::io::Stdout,
-        lines: &Vec<String>,
+        content: Content,
+        sgr: &helpers::SgrIndex,
     ) -> std::io::Result<Self> {
-        // Iterate over the lines in the viewport ...
-        let start = self.start();
-        let end = std::cmp::min(self.end(), lines.len());
-        for (i, l) in lines[start..end].iter().enumerate() {
-            // The final formatted line to be printed to the terminal
-            let mut line = String::from(l);
-
-            let mut found_something = false;
-
-            // If the line matches the search criteria
-            if !self.search.is_empty() {
-                let mut highlighted_line = String::new();
-                let mut remaining = &line[..];
-
-                while let Some(start_idx) = remaining.find(&self.search) {
-                    found_something = true;
-
-                    // Add text before the match
-                    highlighted_line.push_str(&remaining[..start_idx]);
-
-                    // Add the highlighted match
-                    let end_idx = start_idx + self.search.len();
-                    let match_str = &remaining[start_idx..end_idx];
-                    highlighted_line
-                        .push_str(&style(match_str).black().on_white().bold().to_string());
-
-                    // Move the remaining slice to after the match
-                    remaining = &remaining[end_idx..];
+        // Fill the lines of the viewport with the rows, starting from the first visible one ...
+        let height = self.text_height();
+        let mut i = 0;
+        let mut row = self.start();
+        // The colours in effect at the start of the next line, so that consecutive lines don't have to work it out again
+        let mut carried: Option<(usize, helpers::SgrState)> = None;
+        while i < height {
+            // Clear out the rows past the end of the contents
+            let Some((index, l)) = content.get(row) else {
+                let blank = " ".repeat(
+                    self.width
+                        .saturating_sub(self.borders.width_reduction() + 2),
+                );
+                self.print_row(stdout, i, blank)?;
+                i += 1;
+                row += 1;
+                continue;
+            };
+
+            // Start with the colours left on by the lines before
+            let state = match carried.take() {
+                Some((next, state)) if next == index => state,
+                _ => sgr.state_at(index, content.lines.leading(index)),
+            };
+
+            // A long line takes up several lines of the viewport when wrapping
+            for line in self
+                .format_row(index, l, &state)
+                .into_iter()
+                .take(height - i)
+            {
+                self.print_row(stdout, i, line)?;
+                i += 1;
+            }
+            let mut next = state;
+            next.update(l);
+            carried = Some((index + 1, next));
+            row += 1;
+        }
+
+        Ok(self.clone())
+    }
+
+    /// Format the line at the given index into the lines to be printed to the terminal.
+    /// This is a single line, unless the line wraps.
+    fn format_row(&self, index: usize, l: &str, state: &helpers::SgrState) -> Vec<String> {
+        // The final formatted line to be printed to the terminal, with the highlights and search matches applied
+        let (line, found_something) = self.highlight(index, l, state);
+        let mut line = format!("{}{line}", state.to_escape());
+
+        line = helpers::expand_tabs(&line, self.tabstop);
+
+        // Clip the columns scrolled past horizontally, keeping the colours and highlights
+        if self.scroll_col > 0 && !self.wrap {
+            line = helpers::skip_visible(&line, self.scroll_col);
+        }
+
+        if self.search.is_some() && !found_something {
+            line = style(line).dark_grey().to_string();
+        }
+
+        let parts = if self.wrap {
+            helpers::wrap_visible(&line, self.text_width(index))
+        } else {
+            vec![line]
+        };
+
+        parts
+            .into_iter()
+            .enumerate()
+            .map(|(n, mut line)| {
+                // Prepend line numbers if the option was set. Only the first part of a wrapped line is numbered
+                if self.numbered() {
+                    let line_number = self.line_number(index);
+                    let line_number = match n {
+                        0 => style(line_number).dark_grey(),
+                        _ => style(" ".repeat(line_number.len())).dark_grey(),
+                    };
+                    let divider = style("│").dark_grey();
+                    line = format!("{line_number} {divider} {line}");
                 }
 
-                // Add any remaining text after the last match
-                highlighted_line.push_str(remaining);
-                line = highlighted_line;
-            }
+                // Truncate the line to fit in the page width
+                line = helpers::truncate_visible(
+                    &line,
+                    self.width
+                        .saturating_sub(self.borders.width_reduction() + 2),
+                );
 
-            // Clip the string for horizontal scroll
-            if self.scroll_col > 0 {
-                line = match l.split_at_checked(self.scroll_col) {
-                    Some((_, x)) => String::from(x),
-                    None => String::new(),
+                // Don't let the colours of the line spill over into the padding, the borders or the next line
+                if line.contains('\x1b') {
+                    line.push_str("\x1b[0m");
                 }
-            }
 
-            if !self.search.is_empty() && !found_something {
-                line = style(line).dark_grey().to_string();
-            }
+                // Write empty whitespace to the remaining cells to clear previous buffer
+                let remaining = " ".repeat(self.width.saturating_sub(
+                    helpers::visible_width(&line) + self.borders.width_reduction() + 2,
+                ));
+                format!("{line}{remaining}")
+            })
+            .collect()
+    }
 
-            // Prepend line numbers if the option was set
-            if self.show_line_numbers {
-                let line_number = format!("{:>3}", self.start() + i + 1);
-                let line_number = style(line_number).dark_grey();
-                let divider = style("│").dark_grey();
-                line = format!("{line_number} {divider} {line}");
+    /// Apply the pinned highlights and the search matches to the line at the given index.
+    /// Returns the highlighted line, and whether the search pattern matched anything in it.
+    /// The line starts with the given colours, which are restored after each highlight.
+    fn highlight(&self, index: usize, l: &str, state: &helpers::SgrState) -> (String, bool) {
+        if self.search.is_none() && self.highlights.is_empty() {
+            return (String::from(l), false);
+        }
+
+        // Determine the layer each byte of the line is painted with. Later layers are painted over
+        // earlier ones: first the pinned highlights, then the search matches and then the selected match
+        let mut layers: Vec<Option<Layer>> = vec![None; l.len()];
+        for (i, highlight) in self.highlights.iter().enumerate() {
+            for range in highlight.pattern.find_iter(l) {
+                layers[range].fill(Some(Layer::Highlight(i)));
             }
+        }
+        let mut found_something = false;
+        if let Some(pattern) = &self.search {
+            for range in pattern.find_iter(l) {
+                found_something = true;
+                let is_selected = self
+                    .selected
+                    .as_ref()
+                    .is_some_and(|m| m.line == index && m.start == range.start);
+                let layer = if is_selected {
+                    Layer::Selected
+                } else {
+                    Layer::Match
+                };
+                layers[range].fill(Some(layer));
+            }
+        }
 
-            // Truncate the line to fit in the page width
-            line = helpers::truncate_visible(
-                &mut line,
-                self.width
-                    .saturating_sub(self.borders.width_reduction() + 2),
-            );
-
-            // Write empty whitespace to the remaining cells to clear previous buffer
-            let remaining = " ".repeat(self.width.saturating_sub(
-                helpers::visible_width(&line) + self.borders.width_reduction() + 2,
-            ));
-            line = format!("{line}{remaining}");
-
-            // Print out the formatted line
-            let x_offset = if self.show_borders { 1 } else { 0 };
-            let y_offset = if self.show_borders { 1 } else { 0 };
-            stdout
-                .queue(cursor::MoveTo(
-                    helpers::visible_width(&self.borders.left) as u16 + x_offset,
-                    i as u16 + y_offset,
-                ))?
-                .queue(Print(line))?
-                .flush()?;
+        // Paint each run of bytes that share the same layer
+        let mut line = String::new();
+        let mut state = state.clone();
+        let mut start = 0;
+        while start < l.len() {
+            let layer = layers[start];
+            let end = (start..l.len())
+                .find(|&i| layers[i] != layer)
+                .unwrap_or(l.len());
+            let text = &l[start..end];
+            match layer {
+                None => line.push_str(text),
+                Some(Layer::Highlight(i)) => {
+                    let color = self.highlights[i].color;
+                    line.push_str(&style(text).black().on(color).to_string())
+                }
+                Some(Layer::Match) => {
+                    line.push_str(&style(text).black().bold().on_white().to_string())
+                }
+                Some(Layer::Selected) => {
+                    line.push_str(&style(text).black().bold().on_yellow().to_string())
+                }
+            }
+            // Painting a highlight resets the colours, so bring back the ones the line had set
+            state.update(text);
+            if layer.is_some() {
+                line.push_str(&state.to_escape());
+            }
+            start = end;
         }
 
-        Ok(self.clone())
+        (line, found_something)
+    }
+
+    /// Print the formatted line at the i-th row of the viewport
+    fn print_row(
+        &self,
+        stdout: &mut std::io::Stdout,
+        i: usize,
+        line: String,
+    ) -> std::io::Result<()> {
+        let x_offset = if self.show_borders { 1 } else { 0 };
+        let y_offset = if self.show_borders { 1 } else { 0 };
+        stdout
+            .queue(cursor::MoveTo(
+                helpers::visible_width(&self.borders.left) as u16 + x_offset,
+                i as u16 + y_offset,
+            ))?
+            .queue(Print(line))?
+            .flush()?;
+        Ok(())
     }
 
     pub fn render_borders(&self, stdout: &mut std::io::Stdout) -> std::io::Result<()> {